@@ -26,10 +26,10 @@ load_dotenv(
     os.path.join(os.path.dirname(os.path.dirname(os.path.abspath(__file__))), ".env")
 )  # In project root
 
-from fastapi import FastAPI
+from fastapi import FastAPI, Response
 from fastapi.middleware.cors import CORSMiddleware
 from fastapi.responses import StreamingResponse
-from pydantic import BaseModel
+from pydantic import BaseModel, Field
 
 # Import engine with error handling
 try:
@@ -90,6 +90,12 @@ class HealthResponse(BaseModel):
     status: str
     version: str = "1.7.0"
     error: str | None = None
+    progress: int | None = None  # 0-100 while loading; None if unknown
+    app: str = "lightbot-sidecar"  # Tells the app this is its sidecar, not another server
+    # Lets the app check a sidecar it left running is still this process. A
+    # one-file bundle runs under a bootloader, whose PID is the parent's.
+    pid: int = Field(default_factory=os.getpid)
+    ppid: int = Field(default_factory=os.getppid)
 
 
 @asynccontextmanager
@@ -149,6 +155,19 @@ async def health_check() -> HealthResponse:
     return HealthResponse(status="healthy")
 
 
+@app.get("/ready", response_model=HealthResponse)
+async def readiness_check(response: Response) -> HealthResponse:
+    """Readiness endpoint: 503 until the chat engine has finished loading, or
+    for good if it failed to load."""
+    if startup_error:
+        response.status_code = 503
+        return HealthResponse(status="error", error=startup_error)
+    if not chat_engine:
+        response.status_code = 503
+        return HealthResponse(status="initializing")
+    return HealthResponse(status="healthy", progress=100)
+
+
 @app.post("/chat", response_model=ChatResponse)
 async def chat(request: ChatRequest) -> ChatResponse:
     """Non-streaming chat endpoint."""
@@ -209,6 +228,31 @@ async def update_settings(settings: dict) -> dict:
     return {"status": "error", "message": "Engine not initialized"}
 
 
+LOG_LEVELS = ("critical", "error", "warning", "info", "debug")
+
+
+class LogLevelRequest(BaseModel):
+    level: str
+
+
+def set_log_level(level: str) -> None:
+    """Apply a log level to the root, app and uvicorn loggers."""
+    numeric = getattr(logging, level.upper())
+    for name in ("", "uvicorn", "uvicorn.error", "uvicorn.access"):
+        logging.getLogger(name).setLevel(numeric)
+
+
+@app.post("/log-level")
+async def update_log_level(request: LogLevelRequest) -> dict:
+    """Change the log level at runtime (sent by the app when its own level changes)."""
+    level = request.level.lower()
+    if level not in LOG_LEVELS:
+        return {"status": "error", "message": f"Unknown log level '{request.level}'"}
+    set_log_level(level)
+    logger.info(f"Log level set to {level}")
+    return {"status": "updated", "level": level}
+
+
 @app.post("/clip", response_model=ClipResponse)
 async def clip_message(request: ClipRequest) -> ClipResponse:
     """Save a message to the clippings folder."""
@@ -289,11 +333,13 @@ def main():
     parser = argparse.ArgumentParser(description="LightBot Python Sidecar")
     parser.add_argument("--port", type=int, default=8080, help="Port to run on")
     parser.add_argument("--host", type=str, default="127.0.0.1", help="Host to bind to")
+    parser.add_argument("--log-level", type=str.lower, default="info", choices=LOG_LEVELS, help="Log level")
     args = parser.parse_args()
 
     import uvicorn
 
-    uvicorn.run(app, host=args.host, port=args.port, log_level="info")
+    set_log_level(args.log_level)
+    uvicorn.run(app, host=args.host, port=args.port, log_level=args.log_level)
 
 
 if __name__ == "__main__":
@@ -17,6 +17,20 @@ def test_health_check(client):
     data = response.json()
     assert data["status"] == "healthy"
     assert "version" in data
+    assert data["app"] == "lightbot-sidecar"
+
+
+def test_ready_check(client):
+    response = client.get("/ready")
+    assert response.status_code == 200
+    assert response.json()["status"] == "healthy"
+
+
+def test_ready_check_fails_on_startup_error(client, monkeypatch):
+    monkeypatch.setattr("server.startup_error", "model failed to load")
+    response = client.get("/ready")
+    assert response.status_code == 503
+    assert response.json()["status"] == "error"
 
 
 def test_settings_get(client):
@@ -28,3 +42,12 @@ def test_chat_clear(client):
     response = client.post("/chat/clear?session_id=test")
     assert response.status_code == 200
     assert response.json()["status"] == "cleared"
+
+
+def test_log_level_update(client):
+    response = client.post("/log-level", json={"level": "DEBUG"})
+    assert response.status_code == 200
+    assert response.json() == {"status": "updated", "level": "debug"}
+
+    response = client.post("/log-level", json={"level": "verbose"})
+    assert response.json()["status"] == "error"
@@ -1,3 +1,19 @@
+use std::process::Command;
+
 fn main() {
+    // Short commit hash for get_app_version; absent when building outside a git checkout
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=LIGHTBOT_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=LIGHTBOT_TARGET={}", std::env::var("TARGET").unwrap_or_default());
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+
     tauri_build::build()
 }
@@ -0,0 +1,310 @@
+use std::path::PathBuf;
+
+use tauri::{Manager, Runtime};
+
+use crate::config::{self, ConfigState};
+use crate::headless;
+
+/// What the login item launches
+struct LoginItem {
+    /// Bundle identifier; names the LaunchAgent and the .desktop file
+    id: String,
+    /// Product name; names the Run value and the .desktop entry
+    name: String,
+    exe: PathBuf,
+    args: Vec<String>,
+}
+
+fn login_item<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<LoginItem, String> {
+    let exe = current_exe()?;
+    // A login launch doesn't see a LIGHTBOT_HEADLESS exported from a shell, so
+    // carry headless mode over as the flag
+    let args = if headless::is_requested() {
+        vec!["--headless".to_string()]
+    } else {
+        Vec::new()
+    };
+    let config = app.config();
+    Ok(LoginItem {
+        id: config.identifier.clone(),
+        name: config.product_name.clone().unwrap_or_else(|| "LightBot".to_string()),
+        exe,
+        args,
+    })
+}
+
+/// The executable to launch at login. An AppImage runs from a temporary
+/// mount, so use the image itself there.
+fn current_exe() -> Result<PathBuf, String> {
+    #[cfg(target_os = "linux")]
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    std::env::current_exe().map_err(|e| format!("Failed to locate the app executable: {}", e))
+}
+
+/// Default for the first run, before `autostart` is in the config file
+fn env_default() -> Option<bool> {
+    let raw = std::env::var("AUTOSTART").ok()?;
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        other => {
+            log::warn!("Ignoring invalid AUTOSTART '{}', expected true or false", other);
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::PathBuf;
+
+    use super::LoginItem;
+
+    fn plist_path(item: &LoginItem) -> Result<PathBuf, String> {
+        dirs::home_dir()
+            .map(|home| home.join("Library").join("LaunchAgents").join(format!("{}.plist", item.id)))
+            .ok_or_else(|| "No home directory for the LaunchAgent".to_string())
+    }
+
+    fn xml_escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    fn render(item: &LoginItem) -> String {
+        let program = std::iter::once(item.exe.display().to_string())
+            .chain(item.args.iter().cloned())
+            .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+            .collect::<String>();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n    <key>Label</key>\n    <string>{}</string>\n    \
+             <key>ProgramArguments</key>\n    <array>\n{}    </array>\n    \
+             <key>RunAtLoad</key>\n    <true/>\n</dict>\n</plist>\n",
+            xml_escape(&item.id),
+            program
+        )
+    }
+
+    pub fn enable(item: &LoginItem) -> Result<(), String> {
+        let path = plist_path(item)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        std::fs::write(&path, render(item)).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+
+    pub fn disable(item: &LoginItem) -> Result<(), String> {
+        let path = plist_path(item)?;
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to remove {:?}: {}", path, e)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_enabled(item: &LoginItem) -> Result<bool, String> {
+        Ok(plist_path(item)?.exists())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    use super::LoginItem;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    /// Keep reg.exe from flashing a console window
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    fn reg(args: &[&str]) -> Result<bool, String> {
+        Command::new("reg")
+            .args(args)
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map(|output| output.status.success())
+            .map_err(|e| format!("Failed to run reg.exe: {}", e))
+    }
+
+    pub fn enable(item: &LoginItem) -> Result<(), String> {
+        let command = std::iter::once(format!("\"{}\"", item.exe.display()))
+            .chain(item.args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if reg(&["add", RUN_KEY, "/v", &item.name, "/t", "REG_SZ", "/d", &command, "/f"])? {
+            Ok(())
+        } else {
+            Err(format!("Failed to add {} to {}", item.name, RUN_KEY))
+        }
+    }
+
+    pub fn disable(item: &LoginItem) -> Result<(), String> {
+        if !is_enabled(item)? || reg(&["delete", RUN_KEY, "/v", &item.name, "/f"])? {
+            Ok(())
+        } else {
+            Err(format!("Failed to remove {} from {}", item.name, RUN_KEY))
+        }
+    }
+
+    pub fn is_enabled(item: &LoginItem) -> Result<bool, String> {
+        reg(&["query", RUN_KEY, "/v", &item.name])
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::path::PathBuf;
+
+    use super::LoginItem;
+
+    fn desktop_path(item: &LoginItem) -> Result<PathBuf, String> {
+        dirs::config_dir()
+            .map(|dir| dir.join("autostart").join(format!("{}.desktop", item.id)))
+            .ok_or_else(|| "No config directory for the autostart entry".to_string())
+    }
+
+    /// Quote an Exec argument per the desktop entry spec
+    fn exec_quote(arg: &str) -> String {
+        let mut quoted = String::from("\"");
+        for c in arg.chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    pub(super) fn render(item: &LoginItem) -> String {
+        let exec = std::iter::once(item.exe.display().to_string())
+            .chain(item.args.iter().cloned())
+            .map(|arg| exec_quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+            item.name, exec
+        )
+    }
+
+    pub fn enable(item: &LoginItem) -> Result<(), String> {
+        let path = desktop_path(item)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        std::fs::write(&path, render(item)).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+
+    pub fn disable(item: &LoginItem) -> Result<(), String> {
+        let path = desktop_path(item)?;
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to remove {:?}: {}", path, e)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_enabled(item: &LoginItem) -> Result<bool, String> {
+        Ok(desktop_path(item)?.exists())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    use super::LoginItem;
+
+    const UNSUPPORTED: &str = "Launch at login isn't supported on this platform";
+
+    pub fn enable(_item: &LoginItem) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn disable(_item: &LoginItem) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn is_enabled(_item: &LoginItem) -> Result<bool, String> {
+        Ok(false)
+    }
+}
+
+/// Register or remove the login item. Registering again rewrites it, which
+/// picks up a moved executable.
+fn apply<R: Runtime>(app: &tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let item = login_item(app)?;
+    if enabled {
+        platform::enable(&item)?;
+        log::info!("Registered {} to launch at login: {:?} {:?}", item.name, item.exe, item.args);
+    } else {
+        platform::disable(&item)?;
+        log::info!("Removed {} from launch at login", item.name);
+    }
+    Ok(())
+}
+
+/// Make the login item match the config. On the first run `autostart` isn't
+/// in the config yet, so AUTOSTART decides and is saved there.
+pub fn sync_from_config<R: Runtime>(app: &tauri::AppHandle<R>) {
+    // A panic elsewhere while holding the config mustn't take setup down with it
+    let configured = app.state::<ConfigState>().config.lock().unwrap_or_else(|e| e.into_inner()).autostart;
+    let enabled = match configured {
+        Some(enabled) => enabled,
+        None => match env_default() {
+            Some(enabled) => {
+                if let Err(e) = config::update_config(app, |config| config.autostart = Some(enabled)) {
+                    log::warn!("Failed to save the autostart preference: {}", e);
+                }
+                enabled
+            }
+            None => return,
+        },
+    };
+    if let Err(e) = apply(app, enabled) {
+        log::warn!("Failed to update launch at login: {}", e);
+    }
+}
+
+/// Apply a changed `autostart` from set_config
+pub fn apply_config_change<R: Runtime>(app: &tauri::AppHandle<R>, previous: Option<bool>, current: Option<bool>) -> Result<(), String> {
+    match current {
+        Some(enabled) if previous != current => apply(app, enabled),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn get_autostart(app: tauri::AppHandle) -> Result<bool, String> {
+    platform::is_enabled(&login_item(&app)?)
+}
+
+#[tauri::command]
+pub fn set_autostart(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    apply(&app, enabled)?;
+    config::update_config(&app, |config| config.autostart = Some(enabled))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_entry_quotes_exec_arguments() {
+        let item = LoginItem {
+            id: "com.lightbot.tauri".to_string(),
+            name: "LightBot".to_string(),
+            exe: PathBuf::from("/opt/Light $Bot/lightbot"),
+            args: vec!["--headless".to_string()],
+        };
+        let entry = platform::render(&item);
+        assert!(entry.contains("Exec=\"/opt/Light \\$Bot/lightbot\" \"--headless\"\n"));
+        assert!(entry.contains("Name=LightBot\n"));
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const USAGE: &str = "\
+Usage: lightbot [OPTIONS]
+
+Options:
+  --sidecar-port <PORT>  Use a sidecar already running on PORT (LIGHTBOT_SIDECAR_PORT)
+  --log-level <SPEC>     Log level or flexi_logger spec (LOG_LEVEL)
+  --log-dir <DIR>        Directory for log files (LOG_DIR)
+  --headless             Run without a window or tray (LIGHTBOT_HEADLESS)
+
+Flags take precedence over the env vars in parentheses.";
+
+static ARGS: OnceLock<CliArgs> = OnceLock::new();
+
+/// Command-line overrides. Each one wins over its env var, which wins over
+/// the config file and the default.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CliArgs {
+    pub sidecar_port: Option<u16>,
+    pub log_level: Option<String>,
+    pub log_dir: Option<PathBuf>,
+    pub headless: bool,
+}
+
+/// Parse `args` (without the program name). Bad arguments are skipped and
+/// described in the returned errors.
+fn parse<I: IntoIterator<Item = String>>(args: I) -> (CliArgs, Vec<String>) {
+    let mut parsed = CliArgs::default();
+    let mut errors = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| inline_value.clone().or_else(|| args.next()).ok_or_else(|| format!("{} needs a value", name));
+
+        match flag.as_str() {
+            "--headless" => parsed.headless = true,
+            "--sidecar-port" => match value("--sidecar-port") {
+                Ok(raw) => match raw.trim().parse::<u16>() {
+                    Ok(port) if port > 0 => parsed.sidecar_port = Some(port),
+                    _ => errors.push(format!("Invalid --sidecar-port '{}'", raw)),
+                },
+                Err(e) => errors.push(e),
+            },
+            "--log-level" => match value("--log-level") {
+                Ok(raw) if !raw.trim().is_empty() => parsed.log_level = Some(raw.trim().to_string()),
+                Ok(_) => errors.push("--log-level must not be empty".to_string()),
+                Err(e) => errors.push(e),
+            },
+            "--log-dir" => match value("--log-dir") {
+                Ok(raw) if !raw.trim().is_empty() => parsed.log_dir = Some(PathBuf::from(raw)),
+                Ok(_) => errors.push("--log-dir must not be empty".to_string()),
+                Err(e) => errors.push(e),
+            },
+            // Finder adds a process serial number when launching on older macOS
+            other if other.starts_with("-psn_") => {}
+            other => errors.push(format!("Unknown argument '{}'", other)),
+        }
+    }
+
+    (parsed, errors)
+}
+
+/// Parse the process arguments once, before anything reads them. Runs before
+/// the logger exists, so problems go to stderr with the usage text.
+pub fn init() -> &'static CliArgs {
+    ARGS.get_or_init(|| {
+        let (parsed, errors) = parse(std::env::args().skip(1));
+        if !errors.is_empty() {
+            for error in &errors {
+                eprintln!("{}", error);
+            }
+            eprintln!("{}", USAGE);
+        }
+        parsed
+    })
+}
+
+/// The parsed command line (see `init`)
+pub fn args() -> &'static CliArgs {
+    init()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_strs(args: &[&str]) -> (CliArgs, Vec<String>) {
+        parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_flags_with_separate_and_inline_values() {
+        let (args, errors) = parse_strs(&["--sidecar-port", "8765", "--log-level=debug", "--log-dir", "/tmp/logs", "--headless"]);
+        assert!(errors.is_empty());
+        assert_eq!(
+            args,
+            CliArgs {
+                sidecar_port: Some(8765),
+                log_level: Some("debug".to_string()),
+                log_dir: Some(PathBuf::from("/tmp/logs")),
+                headless: true,
+            }
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_skipped_and_reported() {
+        let (args, errors) = parse_strs(&["--sidecar-port=abc", "--verbose", "--headless", "--log-dir"]);
+        assert_eq!(args, CliArgs { headless: true, ..Default::default() });
+        assert_eq!(errors.len(), 3);
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, Runtime};
+
+use crate::autostart;
+use crate::hotkey::{self, HotkeyState};
+use crate::logging::{self, LoggerState};
+use crate::sidecar;
+use crate::window::{self, CloseBehavior, WindowState};
+
+/// config.toml is shared with the Python sidecar, which owns the other tables
+const CONFIG_TABLE: &str = "app";
+
+/// App settings stored in the `[app]` table of config.toml.
+/// Unset fields fall back to the defaults; env vars override them at startup.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Toggle hotkey (GLOBAL_HOTKEY)
+    pub hotkey: Option<String>,
+    /// Log spec (LOG_LEVEL)
+    pub log_level: Option<String>,
+    /// What the close button does (CLOSE_BEHAVIOR)
+    pub close_behavior: Option<CloseBehavior>,
+    /// Port to start the sidecar on if it's free; a random one is picked otherwise
+    pub preferred_port: Option<u16>,
+    /// Keep the window above others (WINDOW_ALWAYS_ON_TOP)
+    pub always_on_top: Option<bool>,
+    /// Run without a tray icon (DISABLE_TRAY); read at startup only
+    pub disable_tray: Option<bool>,
+    /// Launch at login; AUTOSTART seeds it on the first run
+    pub autostart: Option<bool>,
+    /// Index into `list_monitors` to open the window on; the primary monitor
+    /// is used if it's gone
+    pub monitor: Option<usize>,
+}
+
+pub struct ConfigState {
+    pub config: Mutex<Config>,
+}
+
+/// Same file the sidecar reads: config.toml in the project root during
+/// development, otherwise ~/.lightbot/config.toml
+pub fn config_file() -> PathBuf {
+    let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap_or(Path::new("."));
+    let dev_config = project_root.join("config.toml");
+    if dev_config.exists() {
+        return dev_config;
+    }
+
+    dirs::home_dir()
+        .map(|home| home.join(".lightbot").join("config.toml"))
+        .unwrap_or_else(|| PathBuf::from("config.toml"))
+}
+
+fn read_config_table(path: &Path) -> Result<toml::Table, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => contents
+            .parse::<toml::Table>()
+            .map_err(|e| format!("Invalid TOML in {:?}: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(toml::Table::new()),
+        Err(e) => Err(format!("Failed to read {:?}: {}", path, e)),
+    }
+}
+
+/// Load the `[app]` table. Runs before the logger exists, so problems are
+/// returned as a warning for the caller to log; the defaults are used instead.
+pub fn load_config() -> (Config, Option<String>) {
+    let path = config_file();
+    let table = match read_config_table(&path) {
+        Ok(table) => table,
+        Err(e) => return (Config::default(), Some(e)),
+    };
+
+    match table.get(CONFIG_TABLE) {
+        Some(value) => match value.clone().try_into::<Config>() {
+            Ok(config) => (config, None),
+            Err(e) => (
+                Config::default(),
+                Some(format!("Ignoring invalid [{}] table in {:?}: {}", CONFIG_TABLE, path, e)),
+            ),
+        },
+        None => (Config::default(), None),
+    }
+}
+
+/// Write `config` as the `[app]` table, keeping the sidecar's tables intact.
+/// Refuses to touch a file it can't parse rather than clobbering it.
+fn save_config(config: &Config) -> Result<(), String> {
+    let path = config_file();
+    let mut table = read_config_table(&path)?;
+    let value = toml::Value::try_from(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    table.insert(CONFIG_TABLE.to_string(), value);
+
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let contents = toml::to_string(&table).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Change the stored config with `update` and persist it
+pub fn update_config<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    update: impl FnOnce(&mut Config),
+) -> Result<(), String> {
+    let config_state = app.state::<ConfigState>();
+    let mut config = config_state.config.lock().map_err(|e| e.to_string())?;
+    update(&mut config);
+    save_config(&config)
+}
+
+#[tauri::command]
+pub fn get_config(config_state: tauri::State<ConfigState>) -> Result<Config, String> {
+    let config = config_state.config.lock().map_err(|e| e.to_string())?;
+    Ok(config.clone())
+}
+
+/// Validate, persist and apply `config`. The hotkey, log level, close
+/// behavior, always-on-top and autostart apply immediately; the preferred
+/// port on the next sidecar restart; the monitor at the next launch.
+#[tauri::command]
+pub fn set_config(
+    app: tauri::AppHandle,
+    config_state: tauri::State<ConfigState>,
+    hotkey_state: tauri::State<HotkeyState>,
+    logger_state: tauri::State<LoggerState>,
+    window_state: tauri::State<WindowState>,
+    config: Config,
+) -> Result<Config, String> {
+    if let Some(level) = &config.log_level {
+        logging::parse_log_spec(level)?;
+    }
+    if let Some(combo) = &config.hotkey {
+        hotkey::parse_hotkey(combo)?;
+    }
+
+    let previous_autostart = {
+        let mut current = config_state.config.lock().map_err(|e| e.to_string())?;
+        save_config(&config)?;
+        std::mem::replace(&mut *current, config.clone()).autostart
+    };
+    log::info!("Saved config to {:?}", config_file());
+
+    if let Some(combo) = &config.hotkey {
+        hotkey::replace_toggle_hotkey(&app, &hotkey_state, combo)?;
+    }
+    if let Some(level) = &config.log_level {
+        let level = logging::apply_log_level(&logger_state, level)?;
+        sidecar::sync_log_level(&app, &level);
+    }
+    if let Some(behavior) = config.close_behavior {
+        *window_state.close_behavior.lock().map_err(|e| e.to_string())? = behavior;
+    }
+    if let Some(on) = config.always_on_top {
+        window::set_window_always_on_top(&app, on)?;
+    }
+    autostart::apply_config_change(&app, previous_autostart, config.autostart)?;
+
+    Ok(config)
+}
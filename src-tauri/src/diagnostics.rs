@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::env::EnvState;
+use crate::hotkey::{HotkeyState, HotkeyStatus};
+use crate::sidecar::{self, SidecarLatency, SidecarState, SidecarStatusReport, StartupMetrics, SIDECAR_ENV_PREFIX};
+use crate::version::AppVersion;
+use crate::{config, logging, redact};
+
+/// Env vars the app reads. Only whether each is set is reported, never the value.
+const KNOWN_ENV_VARS: &[&str] = &[
+    "AUTOSTART",
+    "CLOSE_BEHAVIOR",
+    "DISABLE_TRAY",
+    "GLOBAL_HOTKEY",
+    "GLOBAL_HOTKEYS",
+    "HOTKEY_DEBOUNCE_MS",
+    "KEEP_SIDECAR_ON_EXIT",
+    "LIGHTBOT_HEADLESS",
+    "LIGHTBOT_SIDECAR_PORT",
+    "LOG_DIR",
+    "LOG_FORMAT",
+    "LOG_LEVEL",
+    "METRICS_ALLOW_REMOTE",
+    "METRICS_HOST",
+    "METRICS_PORT",
+    "QUIT_ON_SIDECAR_FAILURE",
+    "SIDECAR_ALLOW_REMOTE",
+    "SIDECAR_AUTH_TOKEN",
+    "SIDECAR_BINARY_PATH",
+    "SIDECAR_BINARY_STEM",
+    "SIDECAR_CA_CERT",
+    "SIDECAR_CWD",
+    "SIDECAR_ENV_FILE",
+    "SIDECAR_EXTRA_ARGS",
+    "SIDECAR_HEALTH_PATH",
+    "SIDECAR_HOST",
+    "SIDECAR_INITIAL_DELAY_MS",
+    "SIDECAR_LAUNCHER",
+    "SIDECAR_LOG_LEVEL",
+    "SIDECAR_READY_PATH",
+    "SIDECAR_READY_TIMEOUT_SECS",
+    "SIDECAR_RELOAD_PATH",
+    "SIDECAR_REQUEST_TIMEOUT_MS",
+    "SIDECAR_TLS_INSECURE",
+    "SIDECAR_WATCHDOG_FAILURES",
+    "SIDECAR_WATCHDOG_INTERVAL_SECS",
+    "SIDECAR_WATCHDOG_TIMEOUT_MS",
+    "TRAY_CLICK_ACTION",
+    "TRAY_DOUBLE_CLICK_SHOW",
+    "WINDOW_ALWAYS_ON_TOP",
+    "WINDOW_CENTER_ON_SHOW",
+];
+
+/// Everything useful for a bug report, returned by `diagnostics`.
+/// Known secret values are scrubbed from every string.
+#[derive(Clone, Serialize)]
+pub struct Diagnostics {
+    pub version: AppVersion,
+    pub os: &'static str,
+    pub home_dir: Option<String>,
+    pub log_dir: String,
+    pub config_file: String,
+    pub sidecar: SidecarStatusReport,
+    /// Every path checked for the sidecar binary, in order
+    pub sidecar_search_paths: Vec<String>,
+    pub startup_metrics: Option<StartupMetrics>,
+    /// Latest watchdog health round trip, once one has succeeded
+    pub latency: Option<SidecarLatency>,
+    pub hotkeys: Vec<HotkeyStatus>,
+    /// Whether each env var the app reads is set
+    pub env: BTreeMap<String, bool>,
+    /// Required env vars that are unset or empty
+    pub missing_env: Vec<String>,
+}
+
+fn scrub(text: impl AsRef<str>) -> String {
+    redact::redact(text.as_ref()).into_owned()
+}
+
+fn env_presence() -> BTreeMap<String, bool> {
+    let mut env: BTreeMap<String, bool> = KNOWN_ENV_VARS
+        .iter()
+        .map(|name| (name.to_string(), std::env::var_os(name).is_some()))
+        .collect();
+    // Vars forwarded to the sidecar have open-ended names, so list whichever are set
+    for (name, _) in std::env::vars_os() {
+        if let Some(name) = name.to_str().filter(|name| name.starts_with(SIDECAR_ENV_PREFIX)) {
+            env.insert(name.to_string(), true);
+        }
+    }
+    env
+}
+
+#[tauri::command]
+pub fn diagnostics(app: tauri::AppHandle) -> Diagnostics {
+    let sidecar_state = app.state::<SidecarState>();
+    let mut sidecar = sidecar::status_report(&sidecar_state);
+    sidecar.error = sidecar.error.map(scrub);
+    let startup_metrics = sidecar_state.startup_metrics();
+    let latency = sidecar_state.latency();
+    let hotkeys = app
+        .state::<HotkeyState>()
+        .startup_status
+        .lock()
+        .map(|statuses| statuses.clone())
+        .unwrap_or_default();
+    let missing_env = app.state::<EnvState>().missing.lock().map(|m| m.clone()).unwrap_or_default();
+
+    Diagnostics {
+        version: AppVersion::current(),
+        os: std::env::consts::OS,
+        home_dir: dirs::home_dir().map(|home| scrub(home.display().to_string())),
+        log_dir: scrub(logging::resolve_log_dir().display().to_string()),
+        config_file: scrub(config::config_file().display().to_string()),
+        sidecar,
+        sidecar_search_paths: sidecar::resolved_search_paths(&app)
+            .iter()
+            .map(|path| scrub(path.display().to_string()))
+            .collect(),
+        startup_metrics,
+        latency,
+        hotkeys: hotkeys
+            .into_iter()
+            .map(|status| HotkeyStatus { error: status.error.map(scrub), ..status })
+            .collect(),
+        env: env_presence(),
+        missing_env,
+    }
+}
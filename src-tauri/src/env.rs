@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{Emitter, Manager, Runtime};
+
+use crate::redact;
+
+/// Env vars the app and sidecar can't work without. The sidecar currently
+/// reads its models and keys from config.toml, so nothing is required yet;
+/// add names here as they become mandatory.
+const REQUIRED_ENV_VARS: &[&str] = &[];
+
+/// Editors often write a file in several steps; reload once they've settled
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub struct EnvState {
+    /// Required vars that were unset or empty at startup (or at the last reload)
+    pub missing: Mutex<Vec<String>>,
+    /// Watches the loaded .env for changes; dropping it stops the watch
+    pub watcher: Mutex<Option<RecommendedWatcher>>,
+    /// Bumped on every file event; a pending reload only runs if it's still the latest
+    pub reload_generation: AtomicU64,
+}
+
+/// Payload of the `env-reloaded` event
+#[derive(Clone, Serialize)]
+struct EnvReloaded {
+    path: String,
+    missing: Vec<String>,
+}
+
+/// Load .env file from project root (dev) or user home (production).
+/// Returns the path that was loaded, if any. Runs before the logger exists,
+/// so the caller is responsible for logging the result.
+pub fn load_dotenv() -> Option<PathBuf> {
+    // Try project root first (development)
+    let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap_or(Path::new("."));
+    let dev_env = project_root.join(".env");
+    
+    if dev_env.exists() {
+        let _ = dotenvy::from_path(&dev_env);
+        return Some(dev_env);
+    }
+    
+    // Fallback to user home (production)
+    if let Some(home) = dirs::home_dir() {
+        let user_env = home.join(".lightbot").join(".env");
+        if user_env.exists() {
+            let _ = dotenvy::from_path(&user_env);
+            return Some(user_env);
+        }
+    }
+
+    None
+}
+
+/// Check that every required env var is set to a non-empty value.
+/// Logs and returns the missing names; startup continues either way.
+pub fn validate_env() -> Vec<String> {
+    let missing: Vec<String> = REQUIRED_ENV_VARS
+        .iter()
+        .filter(|key| std::env::var(key).map(|v| v.trim().is_empty()).unwrap_or(true))
+        .map(|key| key.to_string())
+        .collect();
+
+    if missing.is_empty() {
+        log::debug!("All required env vars are set");
+    } else {
+        log::warn!("Missing required env vars: {}", missing.join(", "));
+    }
+    missing
+}
+
+/// Re-read `path` over the current environment and tell the UI.
+///
+/// Takes effect immediately: secret redaction, the required-var check, and
+/// anything the sidecar reads (on its next restart via `restart_sidecar`).
+/// Applied only at startup, so re-apply with the dedicated command:
+/// GLOBAL_HOTKEY (`set_global_hotkey`), LOG_LEVEL (`set_log_level`) and
+/// WINDOW_ALWAYS_ON_TOP (`set_always_on_top`). Everything else (GLOBAL_HOTKEYS,
+/// LOG_FORMAT, TRAY_*, WINDOW_CENTER_ON_SHOW, CLOSE_BEHAVIOR) needs a restart.
+/// Keys deleted from the file stay set until the app restarts.
+fn reload_dotenv<R: Runtime>(app: &tauri::AppHandle<R>, path: &Path) {
+    if let Err(e) = dotenvy::from_path_override(path) {
+        log::warn!("Failed to reload {:?}: {}", path, e);
+        return;
+    }
+    redact::refresh_secrets_from_env();
+    log::info!("Reloaded .env from: {:?}", path);
+
+    let missing = validate_env();
+    if let Ok(mut current) = app.state::<EnvState>().missing.lock() {
+        *current = missing.clone();
+    }
+
+    let _ = app.emit(
+        "env-reloaded",
+        EnvReloaded {
+            path: path.display().to_string(),
+            missing,
+        },
+    );
+}
+
+/// Watch the loaded .env and reload it whenever it changes.
+/// The parent directory is watched because editors often replace the file rather than write it in place.
+pub fn watch_dotenv<R: Runtime>(app: &tauri::AppHandle<R>, path: PathBuf) -> notify::Result<()> {
+    let Some(dir) = path.parent().map(Path::to_path_buf) else {
+        return Ok(());
+    };
+
+    let app_handle = app.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!("Error watching {:?}: {}", path, e);
+                return;
+            }
+        };
+        // Compare file names only; some backends report canonicalized paths
+        let touches_env = event.paths.iter().any(|p| p.file_name() == path.file_name());
+        if !touches_env || !(event.kind.is_modify() || event.kind.is_create()) {
+            return;
+        }
+
+        let env_state = app_handle.state::<EnvState>();
+        let generation = env_state.reload_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let app_handle = app_handle.clone();
+        let path = path.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(RELOAD_DEBOUNCE).await;
+            let env_state = app_handle.state::<EnvState>();
+            if env_state.reload_generation.load(Ordering::SeqCst) == generation {
+                reload_dotenv(&app_handle, &path);
+            }
+        });
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    if let Ok(mut current) = app.state::<EnvState>().watcher.lock() {
+        *current = Some(watcher);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_env_validation(env_state: tauri::State<EnvState>) -> Result<Vec<String>, String> {
+    let missing = env_state.missing.lock().map_err(|e| e.to_string())?;
+    Ok(missing.clone())
+}
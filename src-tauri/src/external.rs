@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri_plugin_shell::ShellExt;
+
+use crate::{config, logging};
+
+/// Why `open_external` didn't open its target
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum OpenError {
+    /// Not an http(s) URL or a path inside the log or config directory
+    Rejected(String),
+    /// The target was allowed but the OS couldn't open it
+    Failed(String),
+}
+
+impl std::fmt::Display for OpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rejected(msg) => write!(f, "Refused to open: {}", msg),
+            Self::Failed(msg) => write!(f, "Failed to open: {}", msg),
+        }
+    }
+}
+
+/// Directories whose contents the frontend may open
+fn allowed_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![logging::resolve_log_dir()];
+    if let Some(config_dir) = config::config_file().parent() {
+        dirs.push(config_dir.to_path_buf());
+    }
+    dirs
+}
+
+/// Check `target` against the allowlist: an http(s) URL, or an existing path
+/// inside one of `allowed`. Paths are canonicalized so `..` and symlinks
+/// can't escape. Returns what to hand to the OS.
+fn validate_target(target: &str, allowed: &[PathBuf]) -> Result<String, OpenError> {
+    let target = target.trim();
+    // A single-letter "scheme" is a Windows drive letter, not a URL
+    if let Some(url) = reqwest::Url::parse(target).ok().filter(|url| url.scheme().len() > 1) {
+        return match url.scheme() {
+            "http" | "https" => Ok(url.to_string()),
+            "file" => {
+                let path = url
+                    .to_file_path()
+                    .map_err(|_| OpenError::Rejected(format!("Invalid file URL '{}'", target)))?;
+                validate_path(&path, allowed)
+            }
+            scheme => Err(OpenError::Rejected(format!("Scheme '{}' is not allowed", scheme))),
+        };
+    }
+    validate_path(Path::new(target), allowed)
+}
+
+fn validate_path(path: &Path, allowed: &[PathBuf]) -> Result<String, OpenError> {
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| OpenError::Rejected(format!("{:?}: {}", path, e)))?;
+    let inside = allowed
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| resolved.starts_with(dir));
+    if !inside {
+        return Err(OpenError::Rejected(format!("{:?} is outside the log and config directories", path)));
+    }
+    Ok(resolved.display().to_string())
+}
+
+/// Open an http(s) URL in the browser, or a file or folder from the log or
+/// config directory with its default app. Anything else is rejected, so the
+/// webview doesn't need the shell plugin's `open` permission itself.
+#[tauri::command]
+pub fn open_external(app: tauri::AppHandle, target: String) -> Result<(), OpenError> {
+    let target = validate_target(&target, &allowed_dirs()).inspect_err(|e| log::warn!("{}", e))?;
+    log::info!("Opening {}", target);
+
+    // The shell plugin's open is deprecated in favour of tauri-plugin-opener,
+    // but it's the plugin this app already ships
+    #[allow(deprecated)]
+    app.shell().open(target, None).map_err(|e| OpenError::Failed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_urls_and_allowed_paths_pass() {
+        let dir = std::env::temp_dir().join(format!("lightbot-open-external-{}", std::process::id()));
+        let logs = dir.join("logs");
+        std::fs::create_dir_all(&logs).unwrap();
+        let log_file = logs.join("app.log");
+        std::fs::write(&log_file, "").unwrap();
+        let allowed = vec![logs.clone()];
+
+        assert!(validate_target("https://example.com/docs", &allowed).is_ok());
+        assert!(validate_target(&log_file.display().to_string(), &allowed).is_ok());
+        assert!(validate_target(&logs.display().to_string(), &allowed).is_ok());
+
+        let escape = logs.join("..").join("logs").join("..").display().to_string();
+        assert!(matches!(validate_target(&escape, &allowed), Err(OpenError::Rejected(_))));
+        assert!(matches!(validate_target("javascript:alert(1)", &allowed), Err(OpenError::Rejected(_))));
+        assert!(matches!(validate_target("/etc/passwd", &allowed), Err(OpenError::Rejected(_))));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
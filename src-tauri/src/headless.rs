@@ -0,0 +1,11 @@
+/// Whether to run without a window or tray: `--headless` on the command line
+/// or LIGHTBOT_HEADLESS=1. The main window is still created (hidden), so a
+/// GUI session is still needed on platforms that require one for that.
+pub fn is_requested() -> bool {
+    if crate::cli::args().headless {
+        return true;
+    }
+    std::env::var("LIGHTBOT_HEADLESS")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
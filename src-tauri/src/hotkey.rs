@@ -0,0 +1,541 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{Emitter, Manager, Runtime};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::config::ConfigState;
+use crate::sidecar;
+use crate::window::{
+    apply_always_on_top, show_main_window, toggle_window_visibility, VisibilitySource, WindowState,
+};
+
+const DEFAULT_HOTKEY_MACOS: &str = "Command+Shift+O";
+const DEFAULT_HOTKEY_OTHER: &str = "Ctrl+Shift+O";
+const DEFAULT_HOTKEY_DEBOUNCE_MS: u64 = 250;
+
+pub struct HotkeyState {
+    /// The shortcut bound to the toggle action (the one `set_global_hotkey` replaces)
+    pub current_shortcut: Mutex<Option<Shortcut>>,
+    /// The normalized combo string for `current_shortcut`, for display in the UI
+    pub current_combo: Mutex<Option<String>>,
+    /// Normalized combo of every registered hotkey by shortcut, for logging presses
+    pub active_combos: Mutex<HashMap<Shortcut, String>>,
+    /// Outcome of each registration attempted at startup
+    pub startup_status: Mutex<Vec<HotkeyStatus>>,
+    /// When each hotkey was last pressed, ignored presses included, for debouncing
+    pub last_press: Mutex<HashMap<Shortcut, Instant>>,
+    /// Presses closer together than this are ignored (HOTKEY_DEBOUNCE_MS)
+    pub debounce: Duration,
+}
+
+/// Result of registering a hotkey, emitted to the frontend as `hotkey-status`
+#[derive(Clone, Serialize)]
+pub struct HotkeyStatus {
+    pub action: String,
+    pub combo: String,
+    pub ok: bool,
+    pub error: Option<String>,
+    /// What the user can do about a failure, e.g. the workaround on Wayland
+    pub hint: Option<String>,
+}
+
+/// Shown when registration fails under Wayland, which doesn't let apps grab
+/// global shortcuts; a second launch focuses the running window instead
+const WAYLAND_HINT: &str = "Wayland doesn't let apps register global shortcuts. \
+Bind a shortcut to run `lightbot` in your desktop's keyboard settings instead; launching it again shows the running window.";
+
+/// What a global hotkey does when pressed
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HotkeyAction {
+    /// Show the window, or hide it if it's already focused
+    Toggle,
+    /// Show and focus the window, never hide it
+    Show,
+    /// Restart the Python sidecar
+    Restart,
+    /// Flip the window's always-on-top setting
+    AlwaysOnTop,
+}
+
+impl HotkeyAction {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "toggle" => Some(Self::Toggle),
+            "show" => Some(Self::Show),
+            "restart" => Some(Self::Restart),
+            "always_on_top" => Some(Self::AlwaysOnTop),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Toggle => "toggle",
+            Self::Show => "show",
+            Self::Restart => "restart",
+            Self::AlwaysOnTop => "always_on_top",
+        }
+    }
+}
+
+/// Default toggle hotkey for the given `std::env::consts::OS`. `Command`
+/// is the Windows/Super key outside macOS, so other platforms use Ctrl.
+fn default_hotkey_for(os: &str) -> &'static str {
+    if os == "macos" {
+        DEFAULT_HOTKEY_MACOS
+    } else {
+        DEFAULT_HOTKEY_OTHER
+    }
+}
+
+fn default_hotkey() -> &'static str {
+    default_hotkey_for(std::env::consts::OS)
+}
+
+/// Clean up a user-supplied combo: strip quotes (common when written by some
+/// env tools) and map a `Cmd`/`Command` modifier to the platform's primary
+/// modifier, so a combo written on macOS still works on Windows/Linux
+fn normalize_hotkey_for(raw: &str, os: &str) -> String {
+    let combo = raw.trim().trim_matches(|c| c == '\'' || c == '"');
+    if combo.is_empty() {
+        return String::new();
+    }
+
+    combo
+        .split('+')
+        .map(|token| {
+            let token = token.trim();
+            if token.eq_ignore_ascii_case("cmd") || token.eq_ignore_ascii_case("command") {
+                if os == "macos" { "Command" } else { "Ctrl" }
+            } else {
+                token
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+fn normalize_hotkey(raw: &str) -> String {
+    normalize_hotkey_for(raw, std::env::consts::OS)
+}
+
+/// Normalize and parse a user-supplied combo
+pub fn parse_hotkey(raw: &str) -> Result<Shortcut, String> {
+    let combo = normalize_hotkey(raw);
+    if combo.is_empty() {
+        return Err("Hotkey must not be empty".to_string());
+    }
+    combo
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid hotkey '{}': {}", combo, e))
+}
+
+/// Parse a GLOBAL_HOTKEYS mapping like `toggle=Cmd+Shift+O,show=Cmd+Shift+P`.
+/// Invalid entries are skipped and returned as failed statuses.
+fn parse_hotkey_bindings(raw: &str) -> (Vec<(HotkeyAction, Shortcut, String)>, Vec<HotkeyStatus>) {
+    let mut bindings = Vec::new();
+    let mut failures = Vec::new();
+
+    for entry in raw.trim_matches(|c| c == '\'' || c == '"').split(',') {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            continue;
+        }
+
+        let Some((name, combo)) = entry.split_once('=') else {
+            failures.push(HotkeyStatus::failed("", entry, "Expected action=combo".to_string()));
+            continue;
+        };
+        let Some(action) = HotkeyAction::from_name(name) else {
+            failures.push(HotkeyStatus::failed(name.trim(), combo.trim(), format!("Unknown action '{}'", name.trim())));
+            continue;
+        };
+
+        match parse_hotkey(combo) {
+            Ok(shortcut) => bindings.push((action, shortcut, normalize_hotkey(combo))),
+            Err(e) => failures.push(HotkeyStatus::failed(action.name(), combo.trim(), e)),
+        }
+    }
+
+    (bindings, failures)
+}
+
+impl HotkeyStatus {
+    fn ok(action: &str, combo: &str) -> Self {
+        Self { action: action.to_string(), combo: combo.to_string(), ok: true, error: None, hint: None }
+    }
+
+    fn failed(action: &str, combo: &str, error: String) -> Self {
+        Self { action: action.to_string(), combo: combo.to_string(), ok: false, error: Some(error), hint: None }
+    }
+
+    /// A failure to register a valid combo, with the display server's hint
+    fn registration_failed(action: &str, combo: &str, error: String) -> Self {
+        let hint = (display_server() == Some("wayland")).then(|| WAYLAND_HINT.to_string());
+        Self { hint, ..Self::failed(action, combo, error) }
+    }
+}
+
+/// The Linux display server: XDG_SESSION_TYPE, else whichever display
+/// variable is set. `None` on other platforms.
+fn display_server() -> Option<&'static str> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    display_server_from(
+        std::env::var("XDG_SESSION_TYPE").ok().as_deref(),
+        std::env::var_os("WAYLAND_DISPLAY").is_some(),
+        std::env::var_os("DISPLAY").is_some(),
+    )
+}
+
+fn display_server_from(session_type: Option<&str>, wayland_display: bool, x11_display: bool) -> Option<&'static str> {
+    match session_type.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+        Some("wayland") => Some("wayland"),
+        Some("x11") => Some("x11"),
+        _ if wayland_display => Some("wayland"),
+        _ if x11_display => Some("x11"),
+        _ => None,
+    }
+}
+
+/// Log a registration outcome and forward it to the frontend
+fn report_hotkey_status<R: Runtime>(app: &tauri::AppHandle<R>, status: &HotkeyStatus) {
+    if status.ok {
+        log::info!("Registered global hotkey: {} ({})", status.combo, status.action);
+    } else {
+        log::warn!(
+            "Global hotkey '{}' ({}) not registered: {}",
+            status.combo,
+            status.action,
+            status.error.as_deref().unwrap_or("unknown error")
+        );
+        if let Some(hint) = &status.hint {
+            log::warn!("{}", hint);
+        }
+    }
+
+    if let Err(e) = app.emit("hotkey-status", status) {
+        log::error!("Failed to emit hotkey-status event: {}", e);
+    }
+}
+
+/// Read the single toggle hotkey from GLOBAL_HOTKEY, then `config_hotkey`
+/// (from config.toml), falling back to the default
+fn toggle_hotkey_from_env(config_hotkey: Option<&str>) -> Option<(Shortcut, String)> {
+    let raw = std::env::var("GLOBAL_HOTKEY")
+        .ok()
+        .or(config_hotkey.map(str::to_string))
+        .unwrap_or_else(|| default_hotkey().to_string());
+
+    match parse_hotkey(&raw) {
+        Ok(shortcut) => Some((shortcut, normalize_hotkey(&raw))),
+        Err(e) => {
+            log::warn!("{}. Using default.", e);
+            default_hotkey().parse::<Shortcut>().ok().map(|s| (s, default_hotkey().to_string()))
+        }
+    }
+}
+
+fn run_hotkey_action<R: Runtime>(app: &tauri::AppHandle<R>, action: HotkeyAction) {
+    match action {
+        HotkeyAction::Toggle => toggle_window_visibility(app, VisibilitySource::Hotkey),
+        HotkeyAction::Show => show_main_window(app, VisibilitySource::Hotkey),
+        HotkeyAction::Restart => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = sidecar::restart_managed_sidecar(&app, sidecar::StartCause::User).await {
+                    log::warn!("Hotkey restart of sidecar failed: {}", e);
+                }
+            });
+        }
+        HotkeyAction::AlwaysOnTop => {
+            let current = *app.state::<WindowState>().always_on_top.lock().unwrap();
+            if let Err(e) = apply_always_on_top(app, !current) {
+                log::warn!("Hotkey always-on-top toggle failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Read HOTKEY_DEBOUNCE_MS, falling back to 250ms
+pub fn debounce_from_env() -> Duration {
+    let millis = match std::env::var("HOTKEY_DEBOUNCE_MS") {
+        Ok(raw) => raw.trim().parse::<u64>().unwrap_or_else(|_| {
+            log::warn!("Invalid HOTKEY_DEBOUNCE_MS '{}', using {}ms", raw, DEFAULT_HOTKEY_DEBOUNCE_MS);
+            DEFAULT_HOTKEY_DEBOUNCE_MS
+        }),
+        Err(_) => DEFAULT_HOTKEY_DEBOUNCE_MS,
+    };
+    Duration::from_millis(millis)
+}
+
+/// Record a press of `shortcut` and return the time since its previous one
+/// (`None` for the first). Errs when the press came within the debounce
+/// window and should be ignored. Ignored presses are recorded too, so a held,
+/// auto-repeating key stays debounced until it's released.
+fn record_press(hotkey_state: &HotkeyState, shortcut: &Shortcut) -> Result<Option<Duration>, Duration> {
+    let now = Instant::now();
+    let mut last_press = hotkey_state.last_press.lock().unwrap();
+    let since_last = last_press.insert(*shortcut, now).map(|last| now.duration_since(last));
+    match since_last {
+        Some(elapsed) if elapsed < hotkey_state.debounce => Err(elapsed),
+        _ => Ok(since_last),
+    }
+}
+
+/// The combo a registered shortcut was bound from, or its parsed form
+fn active_combo(hotkey_state: &HotkeyState, shortcut: &Shortcut) -> String {
+    hotkey_state
+        .active_combos
+        .lock()
+        .ok()
+        .and_then(|combos| combos.get(shortcut).cloned())
+        .unwrap_or_else(|| shortcut.into_string())
+}
+
+fn register_hotkey<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    shortcut: Shortcut,
+    combo: &str,
+    action: HotkeyAction,
+) -> Result<(), tauri_plugin_global_shortcut::Error> {
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, shortcut, event| {
+            // Only key-down counts, and auto-repeat or double taps are debounced
+            if event.state() == ShortcutState::Pressed {
+                let hotkey_state = app.state::<HotkeyState>();
+                let combo = active_combo(&hotkey_state, shortcut);
+                match record_press(&hotkey_state, shortcut) {
+                    Ok(Some(elapsed)) => {
+                        log::debug!("Hotkey {} fired ({}), {:?} since last press", combo, action.name(), elapsed)
+                    }
+                    Ok(None) => log::debug!("Hotkey {} fired ({}), first trigger", combo, action.name()),
+                    Err(elapsed) => {
+                        log::debug!(
+                            "Ignoring hotkey {} ({}) press {:?} after the last, within debounce window",
+                            combo,
+                            action.name(),
+                            elapsed
+                        );
+                        return;
+                    }
+                }
+                run_hotkey_action(app, action);
+            }
+        })?;
+    if let Ok(mut combos) = app.state::<HotkeyState>().active_combos.lock() {
+        combos.insert(shortcut, combo.to_string());
+    }
+    Ok(())
+}
+
+/// Register the global hotkeys from GLOBAL_HOTKEYS, or the single toggle
+/// hotkey from GLOBAL_HOTKEY when no mapping is set. Each outcome is emitted
+/// as `hotkey-status` and returned so it can be queried later.
+pub fn setup_global_hotkeys<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    hotkey_state: &HotkeyState,
+) -> Vec<HotkeyStatus> {
+    if let Some(server) = display_server() {
+        log::info!("Display server: {}", server);
+        if server == "wayland" {
+            log::warn!("Running under Wayland, where global hotkeys often fail to register or never fire");
+        }
+    }
+
+    let config_hotkey = app.state::<ConfigState>().config.lock().unwrap().hotkey.clone();
+    let (bindings, mut statuses) = match std::env::var("GLOBAL_HOTKEYS") {
+        Ok(raw) if !raw.trim().is_empty() => parse_hotkey_bindings(&raw),
+        _ => (
+            toggle_hotkey_from_env(config_hotkey.as_deref())
+                .map(|(shortcut, combo)| (HotkeyAction::Toggle, shortcut, combo))
+                .into_iter()
+                .collect(),
+            Vec::new(),
+        ),
+    };
+
+    for (action, shortcut, combo) in bindings {
+        match register_hotkey(app, shortcut, &combo, action) {
+            Ok(()) => {
+                if action == HotkeyAction::Toggle {
+                    *hotkey_state.current_shortcut.lock().unwrap() = Some(shortcut);
+                    *hotkey_state.current_combo.lock().unwrap() = Some(combo.clone());
+                }
+                statuses.push(HotkeyStatus::ok(action.name(), &combo));
+            }
+            Err(e) => statuses.push(HotkeyStatus::registration_failed(action.name(), &combo, e.to_string())),
+        }
+    }
+
+    for status in &statuses {
+        report_hotkey_status(app, status);
+    }
+
+    statuses
+}
+
+/// Swap the toggle hotkey for `raw`. The new combo is registered before the
+/// old one is released, so a failed registration leaves the old binding intact.
+pub fn replace_toggle_hotkey(
+    app: &tauri::AppHandle,
+    hotkey_state: &HotkeyState,
+    raw: &str,
+) -> Result<String, String> {
+    let combo = normalize_hotkey(raw);
+    let new_shortcut = parse_hotkey(raw)?;
+
+    let shortcut_manager = app.global_shortcut();
+    let mut current = hotkey_state.current_shortcut.lock().map_err(|e| e.to_string())?;
+
+    if *current != Some(new_shortcut) {
+        if shortcut_manager.is_registered(new_shortcut) {
+            return Err(format!("Hotkey '{}' is already bound to another LightBot action", combo));
+        }
+
+        register_hotkey(app, new_shortcut, &combo, HotkeyAction::Toggle).map_err(|e| {
+            format!("Failed to register hotkey '{}' (it may be taken by another app): {:?}", combo, e)
+        })?;
+
+        // Unregister the old hotkey if exists
+        if let Some(old_shortcut) = *current {
+            if let Err(e) = shortcut_manager.unregister(old_shortcut) {
+                log::warn!("Failed to unregister old hotkey: {:?}", e);
+            } else {
+                log::info!("Unregistered old hotkey: {:?}", old_shortcut);
+                if let Ok(mut combos) = hotkey_state.active_combos.lock() {
+                    combos.remove(&old_shortcut);
+                }
+            }
+        }
+        *current = Some(new_shortcut);
+    }
+
+    log::info!("Successfully updated hotkey to: {}", combo);
+    *hotkey_state.current_combo.lock().map_err(|e| e.to_string())? = Some(combo.clone());
+
+    Ok(combo)
+}
+
+#[tauri::command]
+pub fn set_global_hotkey(
+    app: tauri::AppHandle,
+    hotkey_state: tauri::State<HotkeyState>,
+    combo: String,
+) -> Result<String, String> {
+    let result = replace_toggle_hotkey(&app, &hotkey_state, &combo);
+    let status = match &result {
+        Ok(normalized) => HotkeyStatus::ok(HotkeyAction::Toggle.name(), normalized),
+        Err(e) if parse_hotkey(&combo).is_ok() => {
+            HotkeyStatus::registration_failed(HotkeyAction::Toggle.name(), &normalize_hotkey(&combo), e.clone())
+        }
+        Err(e) => HotkeyStatus::failed(HotkeyAction::Toggle.name(), &normalize_hotkey(&combo), e.clone()),
+    };
+    report_hotkey_status(&app, &status);
+    result
+}
+
+#[tauri::command]
+pub fn get_hotkey_status(hotkey_state: tauri::State<HotkeyState>) -> Vec<HotkeyStatus> {
+    hotkey_state.startup_status.lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_global_hotkey(hotkey_state: tauri::State<HotkeyState>) -> Option<String> {
+    hotkey_state.current_combo.lock().ok().and_then(|combo| combo.clone())
+}
+
+/// Older name for `set_global_hotkey`, still used by the settings panel
+#[tauri::command]
+pub fn update_hotkey(
+    app: tauri::AppHandle,
+    hotkey_state: tauri::State<HotkeyState>,
+    new_hotkey: String,
+) -> Result<String, String> {
+    replace_toggle_hotkey(&app, &hotkey_state, &new_hotkey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hotkey_strips_quotes() {
+        assert_eq!(parse_hotkey("'Command+Shift+O'"), parse_hotkey("Command+Shift+O"));
+        assert_eq!(parse_hotkey("\"Command+Shift+O\""), parse_hotkey("Command+Shift+O"));
+        assert_eq!(parse_hotkey("  Command+Shift+O  "), parse_hotkey("Command+Shift+O"));
+    }
+
+    #[test]
+    fn parse_hotkey_aliases_cmd() {
+        let shortcut = parse_hotkey("Cmd+Shift+O").expect("Cmd alias should parse");
+        assert_eq!(Ok(shortcut), parse_hotkey("Command+Shift+O"));
+    }
+
+    #[test]
+    fn normalize_maps_cmd_per_platform() {
+        assert_eq!(normalize_hotkey_for("Cmd+Shift+O", "macos"), "Command+Shift+O");
+        assert_eq!(normalize_hotkey_for("Cmd+Shift+O", "windows"), "Ctrl+Shift+O");
+        assert_eq!(normalize_hotkey_for("command+Shift+O", "linux"), "Ctrl+Shift+O");
+        // CmdOrCtrl is resolved per platform by the parser itself
+        assert_eq!(normalize_hotkey_for("CmdOrCtrl+O", "linux"), "CmdOrCtrl+O");
+    }
+
+    #[test]
+    fn default_hotkey_per_platform() {
+        assert_eq!(default_hotkey_for("macos"), "Command+Shift+O");
+        assert_eq!(default_hotkey_for("windows"), "Ctrl+Shift+O");
+        assert_eq!(default_hotkey_for("linux"), "Ctrl+Shift+O");
+        assert!(parse_hotkey(default_hotkey()).is_ok());
+    }
+
+    #[test]
+    fn record_press_reports_elapsed_and_debounces() {
+        let hotkey_state = HotkeyState {
+            current_shortcut: Mutex::new(None),
+            current_combo: Mutex::new(None),
+            active_combos: Mutex::new(HashMap::new()),
+            startup_status: Mutex::new(Vec::new()),
+            last_press: Mutex::new(HashMap::new()),
+            debounce: Duration::from_millis(500),
+        };
+        let toggle = parse_hotkey("Ctrl+Shift+O").unwrap();
+        let show = parse_hotkey("Ctrl+Shift+P").unwrap();
+        assert_eq!(record_press(&hotkey_state, &toggle), Ok(None));
+        assert!(record_press(&hotkey_state, &toggle).is_err());
+        // Another hotkey has its own window
+        assert_eq!(record_press(&hotkey_state, &show), Ok(None));
+
+        // A held key repeats faster than the window, so it stays ignored even
+        // once the first press is further back than the window
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(200));
+            assert!(record_press(&hotkey_state, &toggle).is_err());
+        }
+
+        let earlier = Instant::now() - Duration::from_secs(120);
+        hotkey_state.last_press.lock().unwrap().insert(toggle, earlier);
+        assert!(matches!(record_press(&hotkey_state, &toggle), Ok(Some(elapsed)) if elapsed >= Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn display_server_prefers_session_type() {
+        assert_eq!(display_server_from(Some("wayland"), false, true), Some("wayland"));
+        assert_eq!(display_server_from(Some("X11"), true, false), Some("x11"));
+        assert_eq!(display_server_from(Some("tty"), true, true), Some("wayland"));
+        assert_eq!(display_server_from(None, false, true), Some("x11"));
+        assert_eq!(display_server_from(None, false, false), None);
+    }
+
+    #[test]
+    fn parse_hotkey_rejects_invalid_combos() {
+        assert!(parse_hotkey("").is_err());
+        assert!(parse_hotkey("''").is_err());
+        assert!(parse_hotkey("Command+Shift+NotAKey").is_err());
+        assert!(parse_hotkey("Command+").is_err());
+    }
+}
@@ -0,0 +1,456 @@
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use flexi_logger::writers::{ArcFileLogWriter, FileLogWriter, FileLogWriterHandle};
+use flexi_logger::{
+    Cleanup, Criterion, DeferredNow, Duplicate, FileSpec, FlexiLoggerError, FormatFunction,
+    LogSpecification, Logger, LoggerHandle, Naming,
+};
+use log::Record;
+use serde::Serialize;
+use tauri::{Emitter, Runtime};
+
+use crate::{redact, sidecar};
+
+const DEFAULT_LOG_LEVEL: &str = "info";
+const LOG_FILE_BASENAME: &str = "lightbot";
+const LOG_FILE_MAX_BYTES: u64 = 5 * 1024 * 1024;
+const LOG_FILE_KEEP: usize = 3;
+
+/// How often to look for a rotation of the log files
+const ROTATION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Chunk size when scanning a log file backwards for line breaks
+const TAIL_CHUNK_BYTES: u64 = 8 * 1024;
+/// Upper bound for `read_recent_logs` so the UI can't ask for the whole history
+const MAX_RECENT_LOG_LINES: usize = 5000;
+
+const SIDECAR_LOG_FILE_BASENAME: &str = "sidecar";
+const SIDECAR_LOG_FILE_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const SIDECAR_LOG_FILE_KEEP: usize = 2;
+
+/// Holds the running loggers so their spec can be changed after startup
+pub struct LoggerState {
+    pub handle: Option<LoggerHandle>,
+    pub level: Mutex<String>,
+    pub sidecar_writer: Option<ArcFileLogWriter>,
+    /// Shuts the sidecar log writer down when dropped; kept for the app's lifetime
+    pub _sidecar_writer_handle: Option<FileLogWriterHandle>,
+}
+
+/// Directory where log files are written: `--log-dir`, then LOG_DIR, then
+/// ~/.lightbot/logs. Without a home directory a relative path would land
+/// inside the (read-only) app bundle, so fall back to the temp dir instead.
+pub fn resolve_log_dir() -> PathBuf {
+    if let Some(dir) = log_dir_override() {
+        return dir;
+    }
+    match dirs::home_dir() {
+        Some(home) => home.join(".lightbot").join("logs"),
+        None => std::env::temp_dir().join("lightbot").join("logs"),
+    }
+}
+
+fn log_dir_override() -> Option<PathBuf> {
+    crate::cli::args().log_dir.clone().or_else(|| {
+        std::env::var_os("LOG_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Path of the file currently being written for the given basename.
+/// Rotation renames it to `<basename>_r00000.log` etc. and starts a fresh one.
+fn current_log_file(basename: &str) -> PathBuf {
+    resolve_log_dir().join(format!("{}_rCURRENT.log", basename))
+}
+
+/// Most recently rotated file for `basename`, i.e. the highest `<basename>_rNNNNN.log`
+fn previous_log_file(basename: &str) -> Option<PathBuf> {
+    latest_archive(basename).map(|(_, path)| path)
+}
+
+/// Number and path of the highest `<basename>_rNNNNN.log`
+fn latest_archive(basename: &str) -> Option<(u32, PathBuf)> {
+    let prefix = format!("{}_r", basename);
+    std::fs::read_dir(resolve_log_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let number = name.strip_prefix(&prefix)?.strip_suffix(".log")?.parse::<u32>().ok()?;
+            Some((number, entry.path()))
+        })
+        .max_by_key(|(number, _)| *number)
+}
+
+/// Last `count` lines of `source`, reading backwards in chunks so large
+/// files aren't loaded whole
+fn tail_lines<S: Read + Seek>(source: &mut S, count: usize) -> std::io::Result<Vec<String>> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let len = source.seek(SeekFrom::End(0))?;
+    let mut start = len;
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+    let mut newlines = 0;
+    // One extra newline is needed to know the first wanted line is complete
+    while start > 0 && newlines <= count {
+        let chunk = TAIL_CHUNK_BYTES.min(start);
+        start -= chunk;
+        source.seek(SeekFrom::Start(start))?;
+        let mut chunk_buf = vec![0; chunk as usize];
+        source.read_exact(&mut chunk_buf)?;
+        newlines += chunk_buf.iter().filter(|&&b| b == b'\n').count();
+        chunks.push(chunk_buf);
+    }
+    // Chunks were read from the end backwards
+    let buf: Vec<u8> = chunks.into_iter().rev().flatten().collect();
+
+    let text = String::from_utf8_lossy(&buf);
+    let lines: Vec<&str> = text.lines().collect();
+    // Drop a partial first line when we stopped mid-file
+    let complete = if start > 0 { &lines[1.min(lines.len())..] } else { &lines[..] };
+    let skip = complete.len().saturating_sub(count);
+    Ok(complete[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+/// Parse a log spec, rejecting bare words that aren't a known level.
+///
+/// flexi_logger treats an unknown bare word (e.g. "debgu") as a module name,
+/// which would silently log everything for a module that doesn't exist.
+pub fn parse_log_spec(raw: &str) -> Result<LogSpecification, String> {
+    let spec = raw.trim();
+    if spec.is_empty() {
+        return Err("Log level must not be empty".to_string());
+    }
+
+    if !spec.contains(['=', ',', '/']) && spec.parse::<log::LevelFilter>().is_err() {
+        return Err(format!(
+            "Invalid log level '{}': expected one of off, error, warn, info, debug, trace",
+            spec
+        ));
+    }
+
+    LogSpecification::parse(spec).map_err(|e| format!("Invalid log level '{}': {}", spec, e))
+}
+
+/// The sidecar's `--log-level` for an app log spec: the spec's default level
+/// mapped to Python's names (trace has no equivalent, so it becomes debug)
+pub fn sidecar_log_level(spec: &str) -> &'static str {
+    let level = LogSpecification::parse(spec.trim())
+        .ok()
+        .and_then(|spec| {
+            spec.module_filters()
+                .iter()
+                .find(|filter| filter.module_name.is_none())
+                .map(|filter| filter.level_filter)
+        })
+        .unwrap_or(log::LevelFilter::Info);
+
+    match level {
+        log::LevelFilter::Off => "critical",
+        log::LevelFilter::Error => "error",
+        log::LevelFilter::Warn => "warning",
+        log::LevelFilter::Info => "info",
+        log::LevelFilter::Debug | log::LevelFilter::Trace => "debug",
+    }
+}
+
+/// Newline-delimited JSON (one object per record) for shipping logs to a collector
+pub fn json_format(
+    w: &mut dyn Write,
+    now: &mut DeferredNow,
+    record: &Record,
+) -> Result<(), std::io::Error> {
+    let entry = serde_json::json!({
+        "timestamp": now.format_rfc3339(),
+        "level": record.level().as_str(),
+        "module": record.module_path().unwrap_or("<unnamed>"),
+        "message": redact::redact(&record.args().to_string()),
+    });
+    write!(w, "{}", entry)
+}
+
+/// flexi_logger's detailed format with secret values scrubbed
+pub fn text_format(
+    w: &mut dyn Write,
+    now: &mut DeferredNow,
+    record: &Record,
+) -> Result<(), std::io::Error> {
+    let mut buf = Vec::new();
+    flexi_logger::detailed_format(&mut buf, now, record)?;
+    w.write_all(redact::redact(&String::from_utf8_lossy(&buf)).as_bytes())
+}
+
+/// Pick the file format from LOG_FORMAT (`text` by default, or `json`)
+fn file_format_from_env() -> (FormatFunction, Option<String>) {
+    match std::env::var("LOG_FORMAT") {
+        Ok(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+            "json" => (json_format, None),
+            "" | "text" => (text_format, None),
+            other => (
+                text_format,
+                Some(format!("Ignoring unknown LOG_FORMAT '{}', expected 'text' or 'json'", other)),
+            ),
+        },
+        Err(_) => (text_format, None),
+    }
+}
+
+/// Start the file logger, reading the initial level from `--log-level`, then
+/// LOG_LEVEL, then `config_level` (from config.toml), then the default
+pub fn setup_logger(config_level: Option<&str>) -> Result<(LoggerHandle, String), FlexiLoggerError> {
+    let configured = crate::cli::args()
+        .log_level
+        .clone()
+        .or_else(|| std::env::var("LOG_LEVEL").ok())
+        .or(config_level.map(str::to_string));
+    let (level, rejected) = match configured {
+        Some(raw) => match parse_log_spec(&raw) {
+            Ok(_) => (raw.trim().to_string(), None),
+            Err(e) => (DEFAULT_LOG_LEVEL.to_string(), Some(e)),
+        },
+        None => (DEFAULT_LOG_LEVEL.to_string(), None),
+    };
+    let (file_format, format_warning) = file_format_from_env();
+
+    let handle = Logger::try_with_str(&level)?
+        .log_to_file(
+            FileSpec::default()
+                .directory(resolve_log_dir())
+                .basename(LOG_FILE_BASENAME)
+                .suppress_timestamp(),
+        )
+        .rotate(
+            Criterion::Size(LOG_FILE_MAX_BYTES),
+            Naming::Numbers,
+            Cleanup::KeepLogFiles(LOG_FILE_KEEP),
+        )
+        .append()
+        .duplicate_to_stdout(Duplicate::All)
+        .format_for_files(file_format)
+        .format_for_stdout(text_format)
+        .start()?;
+
+    if dirs::home_dir().is_none() && log_dir_override().is_none() {
+        log::warn!("Could not resolve the home directory, writing logs to {:?}", resolve_log_dir());
+    }
+    if let Some(e) = rejected {
+        log::warn!("Ignoring configured log level: {}. Using '{}'.", e, level);
+    }
+    if let Some(warning) = format_warning {
+        log::warn!("{}", warning);
+    }
+
+    Ok((handle, level))
+}
+
+/// Open the rotating file that receives the sidecar's raw stdout/stderr
+pub fn setup_sidecar_log() -> Result<(ArcFileLogWriter, FileLogWriterHandle), FlexiLoggerError> {
+    FileLogWriter::builder(
+        FileSpec::default()
+            .directory(resolve_log_dir())
+            .basename(SIDECAR_LOG_FILE_BASENAME)
+            .suppress_timestamp(),
+    )
+    .rotate(
+        Criterion::Size(SIDECAR_LOG_FILE_MAX_BYTES),
+        Naming::Numbers,
+        Cleanup::KeepLogFiles(SIDECAR_LOG_FILE_KEEP),
+    )
+    .append()
+    .try_build_with_handle()
+}
+
+/// Payload of the `log-rotated` event
+#[derive(Clone, Serialize)]
+struct LogRotated {
+    /// "app" or "sidecar", as in `get_log_path`
+    log: &'static str,
+    /// The fresh file now being written
+    current: String,
+    /// Where the previous contents went
+    archived: String,
+}
+
+/// Emit `log-rotated` whenever either log file rotates, so tailers can reopen
+/// it. flexi_logger has no rotation callback, so this polls for a new
+/// highest-numbered archive instead.
+pub fn watch_rotation<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let logs = [("app", LOG_FILE_BASENAME), ("sidecar", SIDECAR_LOG_FILE_BASENAME)];
+        let mut latest: Vec<Option<u32>> =
+            logs.iter().map(|(_, basename)| latest_archive(basename).map(|(n, _)| n)).collect();
+
+        loop {
+            tokio::time::sleep(ROTATION_POLL_INTERVAL).await;
+            for ((log, basename), seen) in logs.iter().zip(latest.iter_mut()) {
+                let Some((number, archived)) = latest_archive(basename) else {
+                    continue;
+                };
+                if seen.is_some_and(|seen| seen >= number) {
+                    continue;
+                }
+                *seen = Some(number);
+
+                let payload = LogRotated {
+                    log,
+                    current: current_log_file(basename).display().to_string(),
+                    archived: archived.display().to_string(),
+                };
+                log::debug!("The {} log rotated to {}", log, payload.archived);
+                if let Err(e) = app.emit("log-rotated", payload) {
+                    log::error!("Failed to emit log-rotated event: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Copy a child process stream into the sidecar log line by line, passing
+/// each line to `on_line` as well. Runs on its own thread until the stream
+/// closes (i.e. the sidecar exits).
+pub fn pipe_to_sidecar_log<S, F>(stream: S, mut writer: ArcFileLogWriter, on_line: F)
+where
+    S: Read + Send + 'static,
+    F: Fn(&str) + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let reader = BufReader::new(stream);
+        for line in reader.lines() {
+            match line {
+                Ok(line) => {
+                    on_line(&line);
+                    let _ = writeln!(writer, "{}", redact::redact(&line));
+                }
+                Err(e) => {
+                    log::warn!("Stopped reading sidecar output: {}", e);
+                    break;
+                }
+            }
+        }
+        let _ = writer.flush();
+    });
+}
+
+/// Write out anything still buffered in both log files before exiting
+pub fn flush_logs(logger_state: &LoggerState) {
+    if let Some(handle) = &logger_state.handle {
+        handle.flush();
+    }
+    if let Some(writer) = &logger_state.sidecar_writer {
+        let _ = writer.clone().flush();
+    }
+}
+
+/// Switch the running logger to `level`, returning the trimmed spec
+pub fn apply_log_level(logger_state: &LoggerState, level: &str) -> Result<String, String> {
+    let handle = logger_state
+        .handle
+        .as_ref()
+        .ok_or("File logger is not running")?;
+    let spec = parse_log_spec(level)?;
+    let level = level.trim().to_string();
+
+    let mut current = logger_state.level.lock().map_err(|e| e.to_string())?;
+    log::info!("Changing log level from '{}' to '{}'", *current, level);
+    handle.set_new_spec(spec);
+    *current = level.clone();
+
+    Ok(level)
+}
+
+#[tauri::command]
+pub fn set_log_level(
+    app: tauri::AppHandle,
+    logger_state: tauri::State<LoggerState>,
+    level: String,
+) -> Result<String, String> {
+    let level = apply_log_level(&logger_state, &level)?;
+    sidecar::sync_log_level(&app, &level);
+    Ok(level)
+}
+
+/// Last `lines` lines of the app log, continuing into the previous rotated
+/// file when the current one is shorter than that. Read on a blocking thread
+/// so a large log can't stall the UI.
+#[tauri::command]
+pub async fn read_recent_logs(lines: usize) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || recent_log_lines(lines))
+        .await
+        .map_err(|e| format!("Failed to read logs: {}", e))?
+}
+
+fn recent_log_lines(lines: usize) -> Result<Vec<String>, String> {
+    let lines = lines.min(MAX_RECENT_LOG_LINES);
+    let current = current_log_file(LOG_FILE_BASENAME);
+    let mut recent = match std::fs::File::open(&current) {
+        Ok(mut file) => tail_lines(&mut file, lines).map_err(|e| format!("Failed to read {:?}: {}", current, e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Failed to open {:?}: {}", current, e)),
+    };
+
+    if recent.len() < lines {
+        if let Some(previous) = previous_log_file(LOG_FILE_BASENAME) {
+            let mut file = std::fs::File::open(&previous).map_err(|e| format!("Failed to open {:?}: {}", previous, e))?;
+            let mut older = tail_lines(&mut file, lines - recent.len())
+                .map_err(|e| format!("Failed to read {:?}: {}", previous, e))?;
+            older.append(&mut recent);
+            recent = older;
+        }
+    }
+
+    Ok(recent)
+}
+
+#[tauri::command]
+pub fn get_log_path(which: String) -> Result<String, String> {
+    let path = match which.as_str() {
+        "app" => current_log_file(LOG_FILE_BASENAME),
+        "sidecar" => current_log_file(SIDECAR_LOG_FILE_BASENAME),
+        other => return Err(format!("Unknown log '{}': expected 'app' or 'sidecar'", other)),
+    };
+    Ok(path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn tail_returns_last_lines_across_chunks() {
+        let text: String = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        let tail = tail_lines(&mut Cursor::new(text.into_bytes()), 3).unwrap();
+        assert_eq!(tail, vec!["line 4997", "line 4998", "line 4999"]);
+    }
+
+    #[test]
+    fn tail_spanning_several_chunks_keeps_line_order() {
+        let text: String = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        let tail = tail_lines(&mut Cursor::new(text.into_bytes()), 2000).unwrap();
+        assert_eq!(tail.len(), 2000);
+        assert_eq!(tail.first().map(String::as_str), Some("line 3000"));
+        assert_eq!(tail.last().map(String::as_str), Some("line 4999"));
+    }
+
+    #[test]
+    fn tail_of_short_file_returns_everything() {
+        let tail = tail_lines(&mut Cursor::new(b"first\nsecond".to_vec()), 10).unwrap();
+        assert_eq!(tail, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn sidecar_level_follows_the_default_level_of_the_spec() {
+        assert_eq!(sidecar_log_level("warn"), "warning");
+        assert_eq!(sidecar_log_level("trace"), "debug");
+        assert_eq!(sidecar_log_level("off"), "critical");
+        assert_eq!(sidecar_log_level("error, lightbot::sidecar=debug"), "error");
+        assert_eq!(sidecar_log_level("lightbot=debug"), "info");
+    }
+}
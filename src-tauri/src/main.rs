@@ -1,451 +1,284 @@
-use std::path::Path;
-use std::process::Child;
+mod autostart;
+mod cli;
+mod config;
+mod diagnostics;
+mod env;
+mod external;
+mod headless;
+mod hotkey;
+mod logging;
+mod metrics;
+mod notify;
+mod onboarding;
+mod redact;
+mod shutdown;
+mod sidecar;
+mod tray;
+mod version;
+mod window;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::Mutex;
-use tauri::menu::{CheckMenuItem, Menu, MenuItem};
-use tauri::tray::TrayIconBuilder;
-use tauri::{Emitter, Manager, Runtime};
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri::{Emitter, Manager};
 
-struct SidecarState {
-    port: Mutex<u16>,
-    error: Mutex<Option<String>>,
+/// Payload of the `second-instance` event: the CLI args of a launch that was folded into this one
+#[derive(Clone, serde::Serialize)]
+struct SecondInstance {
+    args: Vec<String>,
+    cwd: String,
 }
 
-struct HotkeyState {
-    current_shortcut: Mutex<Option<Shortcut>>,
-}
-
-struct WindowState {
-    visible_on_all_workspaces: Mutex<bool>,
-}
-
-/// Decode PNG bytes to RGBA image data
-fn load_png_icon(bytes: &[u8]) -> tauri::image::Image<'static> {
-    let img = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
-        .expect("Icon should be valid PNG");
-    let rgba = img.to_rgba8();
-    let (width, height) = rgba.dimensions();
-    tauri::image::Image::new_owned(rgba.into_raw(), width, height)
-}
-
-/// Load .env file from project root (dev) or user home (production)
-fn load_dotenv() {
-    // Try project root first (development)
-    let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap_or(Path::new("."));
-    let dev_env = project_root.join(".env");
-    
-    if dev_env.exists() {
-        println!("Loading .env from project root: {:?}", dev_env);
-        let _ = dotenvy::from_path(&dev_env);
-        return;
-    }
-    
-    // Fallback to user home (production)
-    if let Some(home) = dirs::home_dir() {
-        let user_env = home.join(".lightbot").join(".env");
-        if user_env.exists() {
-            println!("Loading .env from user home: {:?}", user_env);
-            let _ = dotenvy::from_path(&user_env);
-        }
-    }
-}
-
-fn toggle_window_visibility<R: Runtime>(app: &tauri::AppHandle<R>) {
-    if let Some(window) = app.get_webview_window("main") {
-        let is_visible = window.is_visible().unwrap_or(false);
-        let is_focused = window.is_focused().unwrap_or(false);
-
-        if is_visible && is_focused {
-            let _ = window.hide();
-        } else {
-            let _ = window.show();
-            let _ = window.set_focus();
-        }
-    }
-}
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // Flags override the env vars below, so read them first
+    cli::init();
 
-fn setup_system_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
-    // Get current setting from state
-    let window_state = app.state::<WindowState>();
-    let visible_on_all = *window_state.visible_on_all_workspaces.lock().unwrap();
-    
-    // Menu items
-    let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-    let all_workspaces_i = CheckMenuItem::with_id(app, "all_workspaces", "Show on All Workspaces", true, visible_on_all, None::<&str>)?;
-    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-    
-    let menu = Menu::with_items(app, &[&show_i, &all_workspaces_i, &quit_i])?;
+    // Load .env file for configuration (before the logger reads LOG_LEVEL)
+    let dotenv_path = env::load_dotenv();
+    redact::refresh_secrets_from_env();
 
-    let icon_bytes = include_bytes!("../icons/tray_light.png").as_slice();
-    let icon = load_png_icon(icon_bytes);
+    let (app_config, config_warning) = config::load_config();
+    // Before setup, which may write config.toml
+    let first_run = onboarding::is_first_run();
 
-    let _tray = TrayIconBuilder::with_id("main-tray")
-        .icon(icon)
-        .menu(&menu)
-        .on_menu_event(move |app, event| {
-            let event_id = event.id.as_ref();
-            if event_id == "show" {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            } else if event_id == "all_workspaces" {
-                if let Some(window) = app.get_webview_window("main") {
-                    let window_state = app.state::<WindowState>();
-                    let mut current = window_state.visible_on_all_workspaces.lock().unwrap();
-                    let new_value = !*current;
-                    
-                    if let Err(e) = window.set_visible_on_all_workspaces(new_value) {
-                        eprintln!("Failed to set visible_on_all_workspaces: {:?}", e);
-                    } else {
-                        *current = new_value;
-                        println!("Set visible_on_all_workspaces to: {}", new_value);
-                        // Note: CheckMenuItem state is toggled automatically by the OS
-                    }
-                }
-            } else if event_id == "quit" {
-                app.exit(0);
-            }
-        })
-        // No on_tray_icon_event handler - click just shows menu
-        .build(app)?;
-
-    Ok(())
-}
-
-fn setup_global_hotkey<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<Shortcut, Box<dyn std::error::Error>> {
-    // Read hotkey from env, fallback to Command+Shift+O
-    let mut hotkey_str = std::env::var("GLOBAL_HOTKEY").unwrap_or_else(|_| "Command+Shift+O".to_string());
-    
-    // Clean up quotes if present (common when written by some env tools)
-    hotkey_str = hotkey_str.trim_matches(|c| c == '\'' || c == '"').to_string();
-    
-    // Normalize "Cmd" to "Command" for platform compatibility
-    let normalized_hotkey = hotkey_str.replace("Cmd", "Command");
-    
-    let shortcut: Shortcut = match normalized_hotkey.parse() {
-        Ok(s) => s,
+    let (logger_handle, log_level) = match logging::setup_logger(app_config.log_level.as_deref()) {
+        Ok((handle, level)) => (Some(handle), level),
         Err(e) => {
-            eprintln!("Invalid hotkey '{}', error: {:?}. Using default.", normalized_hotkey, e);
-            "Command+Shift+O".parse::<Shortcut>()?
+            eprintln!("Failed to start file logger: {}", e);
+            (None, String::new())
         }
     };
 
-    println!("Registering global hotkey: {}", normalized_hotkey);
-
-    app.global_shortcut()
-        .on_shortcut(shortcut, move |app, _shortcut, event| {
-            if event.state() == ShortcutState::Pressed {
-                toggle_window_visibility(app);
-            }
-        })?;
-
-    Ok(shortcut)
-}
-
-async fn spawn_python_sidecar<R: Runtime>(
-    app: &tauri::AppHandle<R>,
-) -> Result<(Option<Child>, u16), String> {
-    // Check for manual sidecar port (Option 1 - Manual Dev Mode)
-    if let Ok(manual_port) = std::env::var("LIGHTBOT_SIDECAR_PORT") {
-        if let Ok(port) = manual_port.parse::<u16>() {
-            println!("🚀 Using manual Python sidecar on port {}", port);
-            
-            // Verify the manual server is actually responsive
-            let client = reqwest::Client::new();
-            let health_url = format!("http://127.0.0.1:{}/health", port);
-            
-            match client.get(&health_url).timeout(std::time::Duration::from_secs(2)).send().await {
-                Ok(resp) if resp.status().is_success() => {
-                    println!("Verified manual sidecar is healthy on port {}", port);
-                    return Ok((None, port));
-                }
-                _ => {
-                    return Err(format!("Manual sidecar port {} provided via LIGHTBOT_SIDECAR_PORT but server is not responding at {}", port, health_url));
-                }
-            }
-        }
-    }
-
-    // Find an available port
-    let port = portpicker::pick_unused_port().ok_or("No available port")?;
-
-    // Detect current target triple for bundled sidecar
-    let arch = if cfg!(target_arch = "aarch64") { "aarch64" } else { "x86_64" };
-    let triple = format!("{}-apple-darwin", arch);
-    let sidecar_with_triple = format!("python-sidecar-{}", triple);
-
-    // Get the directory of the current executable (for bundled app)
-    let current_exe = std::env::current_exe().ok();
-    let exe_dir = current_exe.as_ref().and_then(|p| p.parent().map(|p| p.to_path_buf()));
-    
-    // Try multiple possible paths for the sidecar binary
-    let mut possible_paths = vec![
-        // Bundled app: sidecar is in same directory as main executable (Contents/MacOS/)
-        exe_dir.as_ref().map(|d| d.join(sidecar_with_triple.clone())),
-        exe_dir.as_ref().map(|d| d.join("python-sidecar")),
-        
-        // Bundled paths via Resource (for older Tauri versions)
-        app.path().resolve(format!("bin/{}", sidecar_with_triple), tauri::path::BaseDirectory::Resource).ok(),
-        app.path().resolve("bin/python-sidecar", tauri::path::BaseDirectory::Resource).ok(),
-        
-        // Development paths (relative to app directory)
-        app.path().resolve(format!("src-tauri/bin/{}", sidecar_with_triple), tauri::path::BaseDirectory::AppConfig).ok(),
-        app.path().resolve("src-tauri/bin/python-sidecar", tauri::path::BaseDirectory::AppConfig).ok(),
-    ];
-
-    // Add some direct relative paths as fallback for dev
-    possible_paths.push(Some(std::path::PathBuf::from(format!("src-tauri/bin/{}", sidecar_with_triple))));
-    possible_paths.push(Some(std::path::PathBuf::from("src-tauri/bin/python-sidecar")));
-    possible_paths.push(Some(std::path::PathBuf::from(format!("bin/{}", sidecar_with_triple))));
-    possible_paths.push(Some(std::path::PathBuf::from("bin/python-sidecar")));
-
-    // Log debugging info
-    
-    // Additional debugging - check if we're in /Applications
-    if let Ok(exe) = std::env::current_exe() {
-        let exe_str = exe.display().to_string();
-        if exe_str.contains("/Applications/") {
-        } else if exe_str.contains("target/") {
-        } else {
-        }
-    }
-    
-    let mut sidecar_path = None;
-    for path in possible_paths.iter().flatten() {
-        if path.exists() {
-            sidecar_path = Some(path.clone());
-            break;
-        }
-    }
-
-    let sidecar_path = sidecar_path.ok_or_else(|| {
-        let checked_paths: Vec<String> = possible_paths.iter().flatten()
-            .map(|p| p.display().to_string())
-            .collect();
-        let err = format!(
-            "Python sidecar binary not found. Checked paths: {:?}", 
-            checked_paths
-        );
-        err
-    })?;
-
-    let msg = format!("Spawning Python sidecar from: {:?}", sidecar_path);
-    eprintln!("{}", msg);
-
-    // Spawn the Python sidecar process
-    let mut command = std::process::Command::new(sidecar_path);
-    command.arg("--port").arg(port.to_string());
-
-    #[cfg(target_os = "macos")]
-    {
-        command.env("PYTHONUNBUFFERED", "1");
-    }
-
-    // Sidecar output redirection disabled - matches v1.0.0 behavior
-
-    let child = match command.spawn() {
-        Ok(c) => {
-            c
-        }
+    let (sidecar_writer, sidecar_writer_handle) = match logging::setup_sidecar_log() {
+        Ok((writer, handle)) => (Some(writer), Some(handle)),
         Err(e) => {
-            let err = format!("Failed to spawn sidecar: {}", e);
-            return Err(err);
+            eprintln!("Failed to open sidecar log file: {}", e);
+            (None, None)
         }
     };
 
-    // Wait a bit for the server to start (same as v1.0.0)
-    tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
-
-    // Verify the server is running
-    let client = reqwest::Client::new();
-    let health_url = format!("http://127.0.0.1:{}/health", port);
-
-    let mut retries = 15;
-    while retries > 0 {
-        match client.get(&health_url).timeout(std::time::Duration::from_secs(2)).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                let msg = format!("Python sidecar is healthy on port {}", port);
-                println!("{}", msg);
-                return Ok((Some(child), port));
-            }
-            Ok(resp) => {
-                let status = resp.status();
-                let body = resp.text().await.unwrap_or_default();
-                let _err = format!("Health check returned status: {}, body: {}", status, body);
-                retries -= 1;
-                if retries == 0 {
-                    let final_err = "Sidecar health check failed - /health not returning success".to_string();
-                    return Err(final_err);
-                }
-                tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-            }
-            Err(e) => {
-                let _err = format!("Health check request failed ({} retries left): {}", retries, e);
-                
-                // Check if it's a connection refused error
-                let err_str = format!("{}", e);
-                if err_str.contains("refused") || err_str.contains("Connection") {
-                }
-                
-                retries -= 1;
-                if retries == 0 {
-                    let final_err = "Sidecar health check failed - server not responding".to_string();
-                    return Err(final_err);
-                }
-                tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-            }
-        }
+    log::info!("=== LightBot App Starting ===");
+    log::info!("LightBot {}", version::AppVersion::current());
+    match &dotenv_path {
+        Some(path) => log::info!("Loaded .env from: {:?}", path),
+        None => log::info!("No .env file found, using defaults"),
     }
-
-    Err("Failed to start sidecar".to_string())
-}
-
-#[tauri::command]
-fn get_sidecar_status(state: tauri::State<SidecarState>) -> Result<u16, String> {
-    let port = *state.port.lock().unwrap();
-    if port > 0 {
-        Ok(port)
-    } else {
-        let error = state.error.lock().unwrap();
-        Err(error.clone().unwrap_or_else(|| "Sidecar not started yet".to_string()))
+    if let Some(warning) = config_warning {
+        log::warn!("{}", warning);
     }
-}
-
-#[tauri::command]
-fn update_hotkey(
-    app: tauri::AppHandle,
-    hotkey_state: tauri::State<HotkeyState>,
-    new_hotkey: String,
-) -> Result<String, String> {
-    // Parse the new hotkey
-    let mut hotkey_str = new_hotkey.trim().to_string();
-    hotkey_str = hotkey_str.trim_matches(|c| c == '\'' || c == '"').to_string();
-    let normalized_hotkey = hotkey_str.replace("Cmd", "Command");
-    
-    let new_shortcut: Shortcut = match normalized_hotkey.parse() {
-        Ok(s) => s,
-        Err(e) => {
-            return Err(format!("Invalid hotkey '{}': {:?}", normalized_hotkey, e));
-        }
-    };
-
-    let shortcut_manager = app.global_shortcut();
-    
-    // Unregister the old hotkey if exists
-    let mut current = hotkey_state.current_shortcut.lock().map_err(|e| e.to_string())?;
-    if let Some(old_shortcut) = *current {
-        if let Err(e) = shortcut_manager.unregister(old_shortcut) {
-            eprintln!("Failed to unregister old hotkey: {:?}", e);
-        } else {
-            println!("Unregistered old hotkey: {:?}", old_shortcut);
-        }
+    let missing_env = env::validate_env();
+    let headless = headless::is_requested();
+    if headless {
+        log::info!("Running headless: no window or tray");
     }
-    
-    // Register the new hotkey
-    if let Err(e) = shortcut_manager.on_shortcut(new_shortcut, move |app, _shortcut, event| {
-        if event.state() == ShortcutState::Pressed {
-            toggle_window_visibility(app);
-        }
-    }) {
-        return Err(format!("Failed to register hotkey: {:?}", e));
+    let tray_disabled = tray::disabled_from_env(app_config.disable_tray);
+    if tray_disabled && !headless {
+        log::info!("Tray disabled: closing the window quits, use the global hotkey to show it");
     }
-    
-    println!("Successfully updated hotkey to: {}", normalized_hotkey);
-    *current = Some(new_shortcut);
-    
-    Ok(normalized_hotkey)
-}
 
-#[tauri::command]
-fn set_visible_on_all_workspaces(
-    app: tauri::AppHandle,
-    visible: bool,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
-        window.set_visible_on_all_workspaces(visible)
-            .map_err(|e| format!("Failed to set visible_on_all_workspaces: {:?}", e))?;
-        println!("Set visible_on_all_workspaces to: {}", visible);
-    }
-    Ok(())
-}
+    let sidecar_client = sidecar::http_client_from_env().unwrap_or_else(|e| {
+        log::warn!("{}. Using a default client until the sidecar starts.", e);
+        reqwest::Client::new()
+    });
+
+    let builder = tauri::Builder::default();
+
+    // Must be the first plugin: a second launch forwards its args to this
+    // instance and exits before it creates a tray icon or spawns a sidecar
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+        log::info!("Second instance launched with args {:?} in {}, focusing existing window", args, cwd);
+        window::show_main_window(app, window::VisibilitySource::SecondInstance);
+        if let Err(e) = app.emit("second-instance", SecondInstance { args, cwd }) {
+            log::error!("Failed to emit second-instance event: {}", e);
+        }
+    }));
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    
-    tauri::Builder::default()
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
-        .manage(SidecarState {
-            port: Mutex::new(0),
-            error: Mutex::new(None),
-        })
-        .manage(HotkeyState {
+        .manage(sidecar::SidecarState::new(sidecar_client, sidecar::keep_on_exit_from_env()))
+        .manage(hotkey::HotkeyState {
             current_shortcut: Mutex::new(None),
+            current_combo: Mutex::new(None),
+            active_combos: Mutex::new(HashMap::new()),
+            startup_status: Mutex::new(Vec::new()),
+            last_press: Mutex::new(HashMap::new()),
+            debounce: hotkey::debounce_from_env(),
         })
-        .manage(WindowState {
+        .manage(window::WindowState {
             visible_on_all_workspaces: Mutex::new(false),
+            center_on_show: window::center_on_show_from_env(),
+            always_on_top: Mutex::new(window::initial_always_on_top(app_config.always_on_top)),
+            close_behavior: Mutex::new(window::CloseBehavior::from_env(app_config.close_behavior)),
+            really_quitting: AtomicBool::new(false),
+            headless,
+            tray_disabled,
+            geometry_generation: AtomicU64::new(0),
+        })
+        .manage(metrics::MetricsState {
+            started: std::time::Instant::now(),
+        })
+        .manage(config::ConfigState {
+            config: Mutex::new(app_config),
+        })
+        .manage(env::EnvState {
+            missing: Mutex::new(missing_env),
+            watcher: Mutex::new(None),
+            reload_generation: AtomicU64::new(0),
         })
-        .invoke_handler(tauri::generate_handler![get_sidecar_status, update_hotkey, set_visible_on_all_workspaces])
-        .setup(|app| {
-            // Load .env file for configuration (before hotkey setup)
-            load_dotenv();
+        .manage(logging::LoggerState {
+            handle: logger_handle,
+            level: Mutex::new(log_level),
+            sidecar_writer,
+            _sidecar_writer_handle: sidecar_writer_handle,
+        })
+        .invoke_handler(tauri::generate_handler![
+            sidecar::get_sidecar_status,
+            sidecar::get_full_status,
+            sidecar::restart_sidecar,
+            sidecar::set_sidecar_port,
+            sidecar::is_sidecar_managed,
+            sidecar::reload_sidecar_config,
+            sidecar::sidecar_request,
+            sidecar::check_sidecar_health,
+            sidecar::get_startup_metrics,
+            sidecar::get_sidecar_history,
+            hotkey::update_hotkey,
+            hotkey::set_global_hotkey,
+            hotkey::get_global_hotkey,
+            hotkey::get_hotkey_status,
+            window::set_visible_on_all_workspaces,
+            window::set_always_on_top,
+            window::get_theme,
+            window::list_monitors,
+            window::move_window_to_monitor,
+            logging::set_log_level,
+            logging::get_log_path,
+            logging::read_recent_logs,
+            env::get_env_validation,
+            external::open_external,
+            notify::test_notification,
+            config::get_config,
+            config::set_config,
+            autostart::get_autostart,
+            autostart::set_autostart,
+            version::get_app_version,
+            diagnostics::diagnostics
+        ])
+        .setup(move |app| {
+            // Report missing config; the UI also polls get_env_validation in case it missed this
+            let missing = app.state::<env::EnvState>().missing.lock().unwrap().clone();
+            let _ = app.emit("env-validation", &missing);
+
+            // Same for the system theme, which the UI can also fetch with get_theme
+            window::emit_theme(app.handle(), window::current_theme(app.handle()));
+
+            if let Some(path) = dotenv_path {
+                if let Err(e) = env::watch_dotenv(app.handle(), path) {
+                    log::warn!("Failed to watch .env for changes: {}", e);
+                }
+            }
+
+            autostart::sync_from_config(app.handle());
+
+            // Headless runs only serve the sidecar, so skip everything that shows the window
+            if !headless {
+                // Setup system tray
+                if !tray_disabled {
+                    tray::setup_system_tray(app.handle())?;
+                }
+
+                // Setup global hotkeys
+                let hotkey_state = app.state::<hotkey::HotkeyState>();
+                let hotkey_status = hotkey::setup_global_hotkeys(app.handle(), &hotkey_state);
+                *hotkey_state.startup_status.lock().unwrap() = hotkey_status;
+            }
+
+            // Ctrl+C in a terminal (or SIGTERM) shuts down like the tray's Quit
+            shutdown::exit_on_signal(app.handle());
 
-            // Setup system tray
-            setup_system_tray(app.handle())?;
+            logging::watch_rotation(app.handle());
 
-            // Setup global hotkey
-            if let Ok(shortcut) = setup_global_hotkey(app.handle()) {
-                let hotkey_state = app.state::<HotkeyState>();
-                *hotkey_state.current_shortcut.lock().unwrap() = Some(shortcut);
+            if let Err(e) = metrics::serve_from_env(app.handle()) {
+                log::warn!("Metrics endpoint disabled: {}", e);
             }
 
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                match spawn_python_sidecar(&app_handle).await {
-                    Ok((_child, port)) => {
-                        println!("Python sidecar started on port {}", port);
-                        
-                        // Store the port in state
-                        let state = app_handle.state::<SidecarState>();
-                        *state.port.lock().unwrap() = port;
-                        
-                        // Emit event to frontend that sidecar is ready
-                        if let Err(_e) = app_handle.emit("sidecar-ready", port) {
-                        }
-                    }
-                    Err(e) => {
-                        let err_msg = format!("Failed to start Python sidecar: {}", e);
-                        eprintln!("{}", err_msg);
-                        
-                        // Store the error in state
-                        let state = app_handle.state::<SidecarState>();
-                        *state.error.lock().unwrap() = Some(e.clone());
-                        
-                        if let Err(_err) = app_handle.emit("sidecar-error", e) {
-                        }
-                    }
-                }
+                sidecar::start_sidecar(&app_handle, sidecar::StartCause::Launch).await;
             });
 
+            if headless {
+                return Ok(());
+            }
+
             // Show the main window once everything is set up
             if let Some(window) = app.get_webview_window("main") {
+                window::restore_window_geometry(&window);
+                let preferred_monitor = app
+                    .state::<config::ConfigState>()
+                    .config
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .monitor;
+                if let Some(index) = preferred_monitor {
+                    window::apply_preferred_monitor(&window, index);
+                }
+                let window_state = app.state::<window::WindowState>();
+                if window_state.center_on_show {
+                    window::center_on_cursor_monitor(&window);
+                }
+                if *window_state.always_on_top.lock().unwrap() {
+                    let _ = window.set_always_on_top(true);
+                }
                 let _ = window.show();
                 let _ = window.set_focus();
             }
 
+            if first_run {
+                onboarding::welcome(app.handle());
+            }
+
             Ok(())
         })
-        .on_window_event(|window, event| {
-            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                // Hide window instead of closing (keep running in tray)
+        .on_window_event(|window, event| match event {
+            // Hide window instead of closing (keep running in tray),
+            // unless quitting from the tray or CLOSE_BEHAVIOR=quit
+            tauri::WindowEvent::CloseRequested { api, .. } if window::should_hide_on_close(window) => {
                 let _ = window.hide();
                 api.prevent_close();
+                window::emit_visibility(window, false, window::VisibilitySource::Close);
+            }
+            tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) if window.label() == "main" => {
+                window::schedule_geometry_save(window);
             }
+            tauri::WindowEvent::ThemeChanged(theme) if window.label() == "main" => {
+                log::info!("System theme changed to {:?}", theme);
+                window::emit_theme(window, *theme);
+                tray::set_tray_theme(window.app_handle(), *theme);
+            }
+            tauri::WindowEvent::ScaleFactorChanged { .. } if window.label() == "main" => {
+                tray::refresh_tray_scale(window.app_handle());
+            }
+            _ => {}
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Closing the last window (no tray, or CLOSE_BEHAVIOR=quit) or Cmd+Q
+            // asks to exit without going through shutdown::quit
+            tauri::RunEvent::ExitRequested { code, api, .. } => shutdown::hold_exit(app, code, &api),
+            // Anything that still got past shutdown::shut_down; a no-op after it
+            tauri::RunEvent::Exit => {
+                sidecar::stop_sidecar(app);
+                logging::flush_logs(&app.state::<logging::LoggerState>());
+            }
+            _ => {}
+        });
 }
 
 fn main() {
@@ -0,0 +1,142 @@
+use std::fmt::Write as _;
+use std::net::{TcpListener as StdTcpListener, ToSocketAddrs};
+use std::time::Instant;
+
+use tauri::{Manager, Runtime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::sidecar::{self, SidecarState};
+
+const DEFAULT_METRICS_HOST: &str = "127.0.0.1";
+
+/// Requests are only ever `GET /metrics`; anything longer is dropped
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+pub struct MetricsState {
+    /// When the app started, for `app_uptime_seconds`
+    pub started: Instant,
+}
+
+/// Serve Prometheus metrics on METRICS_HOST:METRICS_PORT if METRICS_PORT is set.
+/// A non-loopback host is rejected unless METRICS_ALLOW_REMOTE=true.
+/// Must be called once the async runtime is running.
+pub fn serve_from_env<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    let Ok(raw_port) = std::env::var("METRICS_PORT") else {
+        return Ok(());
+    };
+    let port = raw_port
+        .trim()
+        .parse::<u16>()
+        .map_err(|_| format!("Invalid METRICS_PORT '{}'", raw_port))?;
+    let host = std::env::var("METRICS_HOST")
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| DEFAULT_METRICS_HOST.to_string());
+
+    let allow_remote = std::env::var("METRICS_ALLOW_REMOTE")
+        .map(|v| v.trim().eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if !sidecar::is_loopback_host(&host) && !allow_remote {
+        return Err(format!(
+            "METRICS_HOST '{}' is not a loopback address; set METRICS_ALLOW_REMOTE=true to allow it",
+            host
+        ));
+    }
+
+    // Bind here so a taken port is reported at startup rather than from the task
+    let addr = (host.trim_start_matches('[').trim_end_matches(']'), port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| format!("Invalid METRICS_HOST '{}'", host))?;
+    let listener = StdTcpListener::bind(addr).map_err(|e| format!("Failed to bind metrics endpoint on {}: {}", addr, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure metrics listener: {}", e))?;
+    log::info!("Serving metrics on http://{}/metrics", addr);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("Failed to start metrics endpoint: {}", e);
+                return;
+            }
+        };
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let body = render(&app);
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = respond(stream, body).await {
+                            log::debug!("Metrics request failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => log::warn!("Failed to accept metrics connection: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Answer one HTTP request: the metrics for `GET /metrics`, 404 for anything else
+async fn respond(mut stream: TcpStream, body: String) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", body),
+        _ => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Current metrics in the Prometheus text exposition format
+fn render<R: Runtime>(app: &tauri::AppHandle<R>) -> String {
+    let state = app.state::<SidecarState>();
+    let uptime = app.state::<MetricsState>().started.elapsed().as_secs_f64();
+    let up = state.get_port() > 0;
+    let restarts = state.restarts();
+    let health_latency_ms = state.health_latency_ms();
+    let startup_ms = state.startup_metrics().map(|m| m.total_ms);
+
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    };
+
+    metric("app_uptime_seconds", "gauge", "Seconds since the app started.", uptime);
+    metric("sidecar_up", "gauge", "Whether the sidecar is running and passed its health check.", if up { 1.0 } else { 0.0 });
+    metric("sidecar_restarts_total", "counter", "Sidecar restarts requested since the app started.", restarts as f64);
+    if let Some(ms) = health_latency_ms {
+        metric("health_check_latency_seconds", "gauge", "Round trip of the last successful sidecar health check.", ms as f64 / 1000.0);
+    }
+    if let Some(ms) = startup_ms {
+        metric("sidecar_startup_seconds", "gauge", "Duration of the last successful sidecar start.", ms as f64 / 1000.0);
+    }
+    out
+}
@@ -0,0 +1,33 @@
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+/// Send a sample notification so a settings panel can show whether
+/// notifications work. Permission is requested first where the platform
+/// asks for it; a denial or a rejected notification is returned as the error.
+#[tauri::command]
+pub fn test_notification(app: tauri::AppHandle) -> Result<(), String> {
+    let notifications = app.notification();
+    let mut permission = notifications
+        .permission_state()
+        .map_err(|e| format!("Failed to check notification permission: {}", e))?;
+    if matches!(permission, PermissionState::Prompt | PermissionState::PromptWithRationale) {
+        permission = notifications
+            .request_permission()
+            .map_err(|e| format!("Failed to request notification permission: {}", e))?;
+    }
+    if permission != PermissionState::Granted {
+        log::warn!("Test notification not sent, permission is {}", permission);
+        return Err(format!("Notification permission is {}", permission));
+    }
+
+    notifications
+        .builder()
+        .title("LightBot")
+        .body("Notifications are working.")
+        .show()
+        .map_err(|e| {
+            log::warn!("Failed to show test notification: {}", e);
+            format!("Failed to show notification: {}", e)
+        })?;
+    log::info!("Sent test notification");
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tauri::{Emitter, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::config;
+use crate::hotkey::HotkeyState;
+use crate::window::{self, VisibilitySource, WindowState};
+
+/// Written to ~/.lightbot once the first-run notice has been shown
+const FIRST_RUN_MARKER: &str = ".first_run_done";
+
+/// Payload of the `first-run` event
+#[derive(Clone, Serialize)]
+struct FirstRun {
+    /// The toggle hotkey, if one registered
+    hotkey: Option<String>,
+    /// Whether the app has a tray icon to live in
+    tray: bool,
+}
+
+fn marker_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lightbot").join(FIRST_RUN_MARKER))
+}
+
+/// Whether this is the first launch: no marker and no config file yet.
+/// ~/.lightbot itself proves nothing since logging and the sidecar create it
+/// on every launch. Call before anything can write config.toml.
+pub fn is_first_run() -> bool {
+    match marker_path() {
+        Some(marker) => !marker.exists() && !config::config_file().exists(),
+        None => false,
+    }
+}
+
+fn write_marker() {
+    let Some(path) = marker_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(e) = std::fs::write(&path, "") {
+        log::warn!("Failed to write {:?}, onboarding will show again: {}", path, e);
+    }
+}
+
+fn notice_body(hotkey: Option<&str>, tray: bool) -> String {
+    let open = match hotkey {
+        Some(combo) => format!("Press {} to show or hide LightBot from anywhere.", combo),
+        None => "No global hotkey is set; add one in the settings.".to_string(),
+    };
+    if tray {
+        format!("{}\nClosing the window keeps LightBot running in the tray.", open)
+    } else {
+        open
+    }
+}
+
+/// Introduce the hotkey and tray on the first launch: emit `first-run`, show
+/// a one-time notification and bring the window up focused. The marker is
+/// written first so a crash part way through can't repeat it.
+pub fn welcome<R: Runtime>(app: &tauri::AppHandle<R>) {
+    log::info!("First run, showing onboarding");
+    write_marker();
+
+    let hotkey = app.state::<HotkeyState>().current_combo.lock().ok().and_then(|combo| combo.clone());
+    let tray = !app.state::<WindowState>().tray_disabled;
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("Welcome to LightBot")
+        .body(notice_body(hotkey.as_deref(), tray))
+        .show()
+    {
+        log::warn!("Failed to show onboarding notification: {}", e);
+    }
+
+    if let Err(e) = app.emit("first-run", FirstRun { hotkey, tray }) {
+        log::error!("Failed to emit first-run event: {}", e);
+    }
+
+    window::show_main_window(app, VisibilitySource::FirstRun);
+}
@@ -0,0 +1,81 @@
+use std::borrow::Cow;
+use std::sync::RwLock;
+
+const REDACTED: &str = "***";
+const SECRET_KEY_SUFFIXES: [&str; 3] = ["_TOKEN", "_KEY", "_SECRET"];
+
+/// Values shorter than this are left alone; scrubbing e.g. "1" or "on"
+/// would mangle unrelated log text without protecting anything.
+const MIN_SECRET_LEN: usize = 4;
+
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Whether an env var name looks like it holds a secret (`*_TOKEN`, `*_KEY`, `*_SECRET`)
+pub fn is_secret_key(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_KEY_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Re-read the secret values from the environment. Call after loading `.env`.
+pub fn refresh_secrets_from_env() {
+    let mut secrets: Vec<String> = std::env::vars()
+        .filter(|(key, value)| is_secret_key(key) && value.len() >= MIN_SECRET_LEN)
+        .map(|(_, value)| value)
+        .collect();
+    // Longest first so a secret containing another secret is scrubbed whole
+    secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    secrets.dedup();
+
+    if let Ok(mut current) = SECRETS.write() {
+        *current = secrets;
+    }
+}
+
+/// Replace every known secret value in `text` with `***`
+pub fn redact(text: &str) -> Cow<'_, str> {
+    match SECRETS.read() {
+        Ok(secrets) => redact_with(&secrets, text),
+        Err(_) => Cow::Borrowed(text),
+    }
+}
+
+fn redact_with<'a, S: AsRef<str>>(secrets: &[S], text: &'a str) -> Cow<'a, str> {
+    let mut result = Cow::Borrowed(text);
+    for secret in secrets {
+        let secret = secret.as_ref();
+        if result.contains(secret) {
+            result = Cow::Owned(result.replace(secret, REDACTED));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubs_known_token_value() {
+        let secrets = ["sk-test-1234567890"];
+        let line = "Calling API with key sk-test-1234567890 at http://127.0.0.1";
+        assert_eq!(
+            redact_with(&secrets, line),
+            "Calling API with key *** at http://127.0.0.1"
+        );
+    }
+
+    #[test]
+    fn leaves_clean_lines_borrowed() {
+        let secrets = ["sk-test-1234567890"];
+        assert!(matches!(redact_with(&secrets, "nothing to see"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn matches_secret_key_names() {
+        assert!(is_secret_key("OPENAI_API_KEY"));
+        assert!(is_secret_key("LIGHTBOT_AUTH_TOKEN"));
+        assert!(is_secret_key("client_secret"));
+        assert!(!is_secret_key("GLOBAL_HOTKEY"));
+        assert!(!is_secret_key("LOG_LEVEL"));
+    }
+}
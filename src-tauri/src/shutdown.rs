@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::{Emitter, Manager, Runtime};
+
+use crate::logging::{self, LoggerState};
+use crate::window::WindowState;
+use crate::{sidecar, tray};
+
+/// Set once a shutdown has begun, so a second Quit or exit request can't start another
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Quit the app: stop the sidecar, flush the logs, then exit. Shared by the
+/// tray's Quit item and the signal handler so neither leaves an orphaned sidecar.
+/// Emits `shutting-down` first, since the sidecar's grace period can make
+/// the exit take a moment.
+pub fn quit<R: Runtime>(app: &tauri::AppHandle<R>) {
+    log::info!("Shutting down");
+    shut_down(app, 0);
+}
+
+/// With QUIT_ON_SIDECAR_FAILURE set, exit with status 1 once the sidecar has
+/// failed for good, so a supervisor (systemd, launchd) restarts the whole
+/// app. Off by default: an interactive app stays in the tray instead.
+pub fn quit_on_sidecar_failure<R: Runtime>(app: &tauri::AppHandle<R>, error: &str) {
+    if !quit_on_sidecar_failure_from_env() {
+        return;
+    }
+    log::error!("Sidecar failed and QUIT_ON_SIDECAR_FAILURE is set, exiting with status 1: {}", error);
+    shut_down(app, 1);
+}
+
+fn quit_on_sidecar_failure_from_env() -> bool {
+    std::env::var("QUIT_ON_SIDECAR_FAILURE")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Hold an exit the event loop started by itself (the last window closed, or
+/// Cmd+Q) and run it through the same shutdown as Quit, so it gets the same
+/// feedback and the sidecar's grace period doesn't freeze the event loop.
+/// The exit `shut_down` requests at the end is let through.
+pub fn hold_exit<R: Runtime>(app: &tauri::AppHandle<R>, code: Option<i32>, api: &tauri::ExitRequestApi) {
+    if app.state::<WindowState>().really_quitting.load(Ordering::SeqCst) {
+        return;
+    }
+    api.prevent_exit();
+    log::info!("Exit requested, shutting down");
+    shut_down(app, code.unwrap_or(0));
+}
+
+fn shut_down<R: Runtime>(app: &tauri::AppHandle<R>, code: i32) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        log::debug!("Already shutting down");
+        return;
+    }
+    if let Err(e) = app.emit("shutting-down", ()) {
+        log::error!("Failed to emit shutting-down event: {}", e);
+    }
+    tray::show_shutting_down(app);
+    // Stopping the sidecar can wait out its SIGTERM grace period, so do it on
+    // a blocking thread rather than stall the caller (often an async task)
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        sidecar::stop_sidecar(&app);
+        log::info!("Backend stopped, exiting");
+        app.state::<WindowState>().really_quitting.store(true, Ordering::SeqCst);
+        logging::flush_logs(&app.state::<LoggerState>());
+        app.exit(code);
+    });
+}
+
+/// Quit on SIGINT or SIGTERM (Ctrl+C on Windows). Must be called once the
+/// async runtime is running, i.e. from `setup` or later.
+pub fn exit_on_signal<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let signal = wait_for_signal().await;
+        log::info!("Received {}", signal);
+        quit(&app);
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => Some(terminate),
+        Err(e) => {
+            log::warn!("Failed to listen for SIGTERM: {}", e);
+            None
+        }
+    };
+    let sigterm = async {
+        match terminate.as_mut() {
+            Some(terminate) => terminate.recv().await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        Ok(()) = tokio::signal::ctrl_c() => "SIGINT",
+        _ = sigterm => "SIGTERM",
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> &'static str {
+    match tokio::signal::ctrl_c().await {
+        Ok(()) => "Ctrl+C",
+        Err(e) => {
+            log::warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending().await
+        }
+    }
+}
@@ -0,0 +1,2864 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::io::Write as _;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::config::ConfigState;
+use crate::hotkey::HotkeyState;
+use crate::window::WindowState;
+use crate::{logging, redact, shutdown, tray};
+
+/// Shared sidecar state, touched by the start/restart/stop commands, the
+/// exit watcher and the tray. Fields are private: the accessors copy values
+/// in or out and release any lock before returning, so no guard can be held
+/// across an `.await`. Read-heavy fields sit behind an `RwLock`.
+pub struct SidecarState {
+    port: RwLock<u16>,
+    error: RwLock<Option<String>>,
+    /// The spawned sidecar process; `None` for a manual (LIGHTBOT_SIDECAR_PORT) sidecar
+    child: Mutex<Option<Child>>,
+    /// Set while a start is in flight so a restart can't spawn a second process
+    starting: AtomicBool,
+    /// Body of the last successful startup health check
+    health: Mutex<Option<HealthInfo>>,
+    /// Bumped on every start so an old exit watcher stops after a restart
+    generation: AtomicU64,
+    /// Phase timings of the last successful start
+    metrics: Mutex<Option<StartupMetrics>>,
+    /// Where the sidecar is reached; refreshed from the env on every start
+    endpoint: RwLock<SidecarEndpoint>,
+    /// Shared by every request to the sidecar so connections are pooled, with
+    /// the timeouts, auth and TLS settings applied; built at setup and rebuilt
+    /// from the env along with `endpoint`
+    client: RwLock<reqwest::Client>,
+    /// Restarts requested via `restart_sidecar` or the hotkey
+    restarts: AtomicU64,
+    /// Round trip of the most recent successful health request (startup or on demand)
+    health_latency_ms: Mutex<Option<u64>>,
+    /// Latest watchdog round trip and its rolling average, as last emitted in `sidecar-latency`
+    latency: Mutex<Option<SidecarLatency>>,
+    /// When the last failure notification was shown, so a crash loop can't spam them
+    last_failure_notice: Mutex<Option<Instant>>,
+    /// Latest lifecycle status, for callers that wait for the sidecar to settle
+    status: tokio::sync::watch::Sender<SidecarStatus>,
+    /// The last HISTORY_CAPACITY lifecycle events, oldest first
+    history: Mutex<VecDeque<SidecarEvent>>,
+    /// Leave the sidecar running when the app quits (KEEP_SIDECAR_ON_EXIT or the tray)
+    keep_on_exit: AtomicBool,
+    /// PID of a sidecar left running by a previous launch and re-adopted by this one
+    adopted_pid: Mutex<Option<u32>>,
+    /// Whether the running sidecar is ours to restart and stop: spawned or
+    /// re-adopted, rather than external (LIGHTBOT_SIDECAR_PORT or reconnected)
+    managed: AtomicBool,
+    /// Latest `sidecar-progress` value while starting
+    progress: Mutex<Option<f64>>,
+    /// Latest `sidecar-error` payload, cleared once the sidecar is ready
+    start_error: Mutex<Option<SidecarStartError>>,
+    /// Port the next spawn must bind, set by `set_sidecar_port`
+    requested_port: Mutex<Option<u16>>,
+}
+
+impl SidecarState {
+    pub fn new(client: reqwest::Client, keep_on_exit: bool) -> Self {
+        SidecarState {
+            port: RwLock::new(0),
+            error: RwLock::new(None),
+            child: Mutex::new(None),
+            starting: AtomicBool::new(false),
+            health: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            metrics: Mutex::new(None),
+            endpoint: RwLock::new(SidecarEndpoint::default()),
+            client: RwLock::new(client),
+            restarts: AtomicU64::new(0),
+            health_latency_ms: Mutex::new(None),
+            latency: Mutex::new(None),
+            last_failure_notice: Mutex::new(None),
+            status: tokio::sync::watch::Sender::new(SidecarStatus::Starting),
+            history: Mutex::new(VecDeque::new()),
+            keep_on_exit: AtomicBool::new(keep_on_exit),
+            adopted_pid: Mutex::new(None),
+            managed: AtomicBool::new(false),
+            progress: Mutex::new(None),
+            start_error: Mutex::new(None),
+            requested_port: Mutex::new(None),
+        }
+    }
+
+    /// Port of the running sidecar; 0 while there isn't one
+    pub fn get_port(&self) -> u16 {
+        *read(&self.port)
+    }
+
+    pub fn set_port(&self, port: u16) {
+        *write(&self.port) = port;
+    }
+
+    /// Clear the port, returning the old one
+    pub fn take_port(&self) -> u16 {
+        std::mem::take(&mut *write(&self.port))
+    }
+
+    /// Why the last start failed or the sidecar stopped, if it did
+    pub fn get_error(&self) -> Option<String> {
+        read(&self.error).clone()
+    }
+
+    pub fn set_error(&self, error: Option<String>) {
+        *write(&self.error) = error;
+    }
+
+    /// Publish a lifecycle status to anyone waiting on the sidecar
+    pub fn set_status(&self, status: SidecarStatus) {
+        self.status.send_replace(status);
+    }
+
+    pub fn endpoint(&self) -> SidecarEndpoint {
+        read(&self.endpoint).clone()
+    }
+
+    /// The shared sidecar client; cheap to clone
+    pub fn client(&self) -> reqwest::Client {
+        read(&self.client).clone()
+    }
+
+    /// Swap in the endpoint and client resolved for a new start
+    fn set_connection(&self, endpoint: SidecarEndpoint, client: reqwest::Client) {
+        *write(&self.endpoint) = endpoint;
+        *write(&self.client) = client;
+    }
+
+    pub fn startup_metrics(&self) -> Option<StartupMetrics> {
+        lock(&self.metrics).clone()
+    }
+
+    pub fn health_latency_ms(&self) -> Option<u64> {
+        *lock(&self.health_latency_ms)
+    }
+
+    pub fn latency(&self) -> Option<SidecarLatency> {
+        lock(&self.latency).clone()
+    }
+
+    pub fn history(&self) -> Vec<SidecarEvent> {
+        lock(&self.history).iter().cloned().collect()
+    }
+
+    fn take_child(&self) -> Option<Child> {
+        lock(&self.child).take()
+    }
+
+    fn take_adopted_pid(&self) -> Option<u32> {
+        lock(&self.adopted_pid).take()
+    }
+
+    pub fn is_managed(&self) -> bool {
+        self.managed.load(Ordering::SeqCst)
+    }
+
+    /// Whether a start is in flight
+    pub fn is_starting(&self) -> bool {
+        self.starting.load(Ordering::SeqCst)
+    }
+
+    /// Restarts requested since launch
+    pub fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::SeqCst)
+    }
+
+    pub fn keep_on_exit(&self) -> bool {
+        self.keep_on_exit.load(Ordering::SeqCst)
+    }
+
+    /// Flip whether the sidecar outlives the app, returning the new setting
+    pub fn toggle_keep_on_exit(&self) -> bool {
+        !self.keep_on_exit.fetch_xor(true, Ordering::SeqCst)
+    }
+
+    /// Everything the lifecycle events have carried so far, for a listener
+    /// that registered after they were sent
+    pub fn full_status(&self) -> SidecarFullStatus {
+        SidecarFullStatus {
+            status: self.status.borrow().clone(),
+            port: self.get_port(),
+            health: lock(&self.health).clone(),
+            progress: *lock(&self.progress),
+            error_detail: lock(&self.start_error).clone(),
+            managed: self.is_managed(),
+        }
+    }
+}
+
+/// Host and health routes used to reach the sidecar
+#[derive(Clone, Debug)]
+pub struct SidecarEndpoint {
+    /// `http` or `https`
+    pub scheme: String,
+    pub host: String,
+    /// Liveness: answers as soon as the server is up
+    pub health_path: String,
+    /// Readiness: answers once the sidecar can serve requests; startup waits
+    /// on this when set, otherwise on `health_path`
+    pub ready_path: Option<String>,
+    /// Re-reads the sidecar's config in place; POSTed by `reload_sidecar_config`
+    pub reload_path: String,
+}
+
+impl Default for SidecarEndpoint {
+    fn default() -> Self {
+        SidecarEndpoint {
+            scheme: "http".to_string(),
+            host: DEFAULT_SIDECAR_HOST.to_string(),
+            health_path: DEFAULT_HEALTH_PATH.to_string(),
+            ready_path: None,
+            reload_path: DEFAULT_RELOAD_PATH.to_string(),
+        }
+    }
+}
+
+impl SidecarEndpoint {
+    /// Read SIDECAR_HOST, SIDECAR_HEALTH_PATH, SIDECAR_READY_PATH and
+    /// SIDECAR_RELOAD_PATH. The host may start with `https://` (or `http://`).
+    /// A non-loopback host is rejected unless SIDECAR_ALLOW_REMOTE=true, so the
+    /// app can't be pointed at another machine by accident.
+    pub fn from_env() -> Result<Self, String> {
+        let raw_host = std::env::var("SIDECAR_HOST")
+            .map(|h| h.trim().to_string())
+            .ok()
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| DEFAULT_SIDECAR_HOST.to_string());
+        let (scheme, host) = split_scheme(&raw_host)?;
+        let health_path = route_from_env("SIDECAR_HEALTH_PATH").unwrap_or_else(|| DEFAULT_HEALTH_PATH.to_string());
+        let ready_path = route_from_env("SIDECAR_READY_PATH");
+        let reload_path = route_from_env("SIDECAR_RELOAD_PATH").unwrap_or_else(|| DEFAULT_RELOAD_PATH.to_string());
+
+        let allow_remote = std::env::var("SIDECAR_ALLOW_REMOTE")
+            .map(|v| v.trim().eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if !is_loopback_host(&host) && !allow_remote {
+            return Err(format!(
+                "SIDECAR_HOST '{}' is not a loopback address; set SIDECAR_ALLOW_REMOTE=true to allow it",
+                host
+            ));
+        }
+
+        Ok(SidecarEndpoint { scheme, host, health_path, ready_path, reload_path })
+    }
+
+    pub fn base_url(&self, port: u16) -> String {
+        // IPv6 literals need brackets in URLs
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            format!("{}://[{}]:{}", self.scheme, self.host, port)
+        } else {
+            format!("{}://{}:{}", self.scheme, self.host, port)
+        }
+    }
+
+    pub fn health_url(&self, port: u16) -> String {
+        format!("{}{}", self.base_url(port), self.health_path)
+    }
+
+    pub fn reload_url(&self, port: u16) -> String {
+        format!("{}{}", self.base_url(port), self.reload_path)
+    }
+
+    /// URL that startup polls until it succeeds
+    pub fn ready_url(&self, port: u16) -> String {
+        let path = self.ready_path.as_deref().unwrap_or(&self.health_path);
+        format!("{}{}", self.base_url(port), path)
+    }
+}
+
+/// Split an optional `http://` or `https://` off a SIDECAR_HOST value
+fn split_scheme(raw: &str) -> Result<(String, String), String> {
+    let Some((scheme, host)) = raw.split_once("://") else {
+        return Ok(("http".to_string(), raw.to_string()));
+    };
+    let scheme = scheme.to_ascii_lowercase();
+    if scheme != "http" && scheme != "https" {
+        return Err(format!("Unsupported scheme '{}' in SIDECAR_HOST, expected http or https", scheme));
+    }
+    Ok((scheme, host.trim_end_matches('/').to_string()))
+}
+
+/// Client for talking to the sidecar. Requests carry SIDECAR_AUTH_TOKEN as a
+/// bearer token when it's set. SIDECAR_CA_CERT adds a PEM CA bundle to
+/// trust; SIDECAR_TLS_INSECURE=true accepts any certificate, for self-signed
+/// development setups only.
+pub fn http_client_from_env() -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(CLIENT_CONNECT_TIMEOUT)
+        .timeout(CLIENT_TIMEOUT);
+
+    if let Some(token) = std::env::var("SIDECAR_AUTH_TOKEN").ok().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()) {
+        let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|_| "SIDECAR_AUTH_TOKEN contains characters not allowed in a header".to_string())?;
+        value.set_sensitive(true);
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+        builder = builder.default_headers(headers);
+    }
+
+    if let Some(path) = std::env::var_os("SIDECAR_CA_CERT").filter(|p| !p.is_empty()) {
+        let path = PathBuf::from(path);
+        let pem = std::fs::read(&path).map_err(|e| format!("Failed to read SIDECAR_CA_CERT {:?}: {}", path, e))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid SIDECAR_CA_CERT {:?}: {}", path, e))?;
+        log::info!("Trusting {} CA certificate(s) from {:?} for the sidecar", certs.len(), path);
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    let insecure = std::env::var("SIDECAR_TLS_INSECURE")
+        .map(|v| v.trim().eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if insecure {
+        log::warn!("SIDECAR_TLS_INSECURE is set: sidecar certificates are not verified. Use this for development only.");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder.build().map_err(|e| format!("Failed to build the sidecar HTTP client: {}", e))
+}
+
+/// Lock `mutex`, taking the data back if a thread panicked while holding it.
+/// Every writer leaves the sidecar state consistent between statements, so a
+/// poisoned lock is still safe to use and shouldn't take the caller down too.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        log::warn!("Recovering sidecar state after a panic while it was locked");
+        poisoned.into_inner()
+    })
+}
+
+/// `lock` for the read side of an `RwLock`
+fn read<T>(rwlock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    rwlock.read().unwrap_or_else(|poisoned| {
+        log::warn!("Recovering sidecar state after a panic while it was locked");
+        poisoned.into_inner()
+    })
+}
+
+/// `lock` for the write side of an `RwLock`
+fn write<T>(rwlock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    rwlock.write().unwrap_or_else(|poisoned| {
+        log::warn!("Recovering sidecar state after a panic while it was locked");
+        poisoned.into_inner()
+    })
+}
+
+/// A route from `key`, with a leading '/' added if missing; `None` if unset or empty
+fn route_from_env(key: &str) -> Option<String> {
+    std::env::var(key)
+        .map(|p| p.trim().to_string())
+        .ok()
+        .filter(|p| !p.is_empty())
+        .map(|p| if p.starts_with('/') { p } else { format!("/{}", p) })
+}
+
+pub fn is_loopback_host(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<std::net::IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(false)
+}
+
+/// JSON body of the sidecar's `/health` endpoint
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HealthInfo {
+    /// "healthy", "initializing" (chat engine still loading) or "error"
+    pub status: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Loading progress (0–100) while the sidecar initializes
+    #[serde(default)]
+    pub progress: Option<f64>,
+    /// SIDECAR_APP_ID for a LightBot sidecar
+    #[serde(default)]
+    pub app: Option<String>,
+    /// PID of the sidecar process, and of its parent (a one-file bundle's bootloader)
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default)]
+    pub ppid: Option<u32>,
+}
+
+impl HealthInfo {
+    /// Parse a /health body; an empty or non-JSON body gives `None`
+    fn parse(body: &str) -> Option<Self> {
+        serde_json::from_str(body).ok()
+    }
+
+    /// Whether this body came from a ready LightBot sidecar
+    fn is_ready_sidecar(&self) -> bool {
+        self.status == "healthy" && self.app.as_deref() == Some(SIDECAR_APP_ID)
+    }
+
+    /// Whether this body came from process `pid`, as spawned: the sidecar
+    /// itself or the bootloader it runs under
+    fn is_process(&self, pid: u32) -> bool {
+        self.pid == Some(pid) || self.ppid == Some(pid)
+    }
+}
+
+/// Payload of the `sidecar-ready` event
+#[derive(Clone, Serialize)]
+struct SidecarReady {
+    port: u16,
+    health: Option<HealthInfo>,
+}
+
+/// Payload of the `sidecar-progress` event, sent on every startup poll.
+/// `progress` is `None` (indeterminate) when the body doesn't report one.
+#[derive(Clone, Serialize)]
+struct SidecarProgress {
+    progress: Option<f64>,
+}
+
+fn emit_progress<R: Runtime>(app: &tauri::AppHandle<R>, health: Option<&HealthInfo>) {
+    let progress = health.and_then(|h| h.progress).map(|p| p.clamp(0.0, 100.0));
+    *lock(&app.state::<SidecarState>().progress) = progress;
+    if let Err(e) = app.emit("sidecar-progress", SidecarProgress { progress }) {
+        log::error!("Failed to emit sidecar-progress event: {}", e);
+    }
+}
+
+/// Why the sidecar isn't running, emitted to the frontend as `sidecar-error`
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum SidecarStartError {
+    /// No sidecar binary at any candidate path; the UI shows how to build it
+    #[serde(rename_all = "camelCase")]
+    BinaryNotFound {
+        message: String,
+        /// Where the binary should be for this kind of build
+        expected_path: String,
+        tried_paths: Vec<String>,
+        build_hint: String,
+    },
+    /// The sidecar didn't become healthy before the readiness deadline
+    #[serde(rename_all = "camelCase")]
+    HealthTimeout {
+        message: String,
+        timeout_secs: u64,
+        /// Why the last health check failed: refused means it never bound,
+        /// a timeout means it's up but wedged
+        last_failure: Option<HealthFailure>,
+    },
+    /// The sidecar died after it became ready
+    Exited { message: String, exit: SidecarExit },
+    /// Any other startup failure
+    Failed { message: String },
+}
+
+impl SidecarStartError {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::BinaryNotFound { message, .. }
+            | Self::HealthTimeout { message, .. }
+            | Self::Exited { message, .. }
+            | Self::Failed { message } => message,
+        }
+    }
+}
+
+impl From<String> for SidecarStartError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
+}
+
+impl From<&str> for SidecarStartError {
+    fn from(message: &str) -> Self {
+        Self::Failed { message: message.to_string() }
+    }
+}
+
+impl std::fmt::Display for SidecarStartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Why a health request failed, so a sidecar that's still starting can be
+/// told apart from one that's wedged
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthFailure {
+    /// Nothing listening yet; normal while the sidecar boots
+    ConnectionRefused,
+    /// No answer in time; alarming once the server is up
+    Timeout,
+    /// Answered with a non-success status
+    Status,
+    /// Any other transport error
+    Transport,
+}
+
+impl HealthFailure {
+    fn classify(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return Self::Timeout;
+        }
+        let mut source = std::error::Error::source(error);
+        while let Some(cause) = source {
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                if io.kind() == std::io::ErrorKind::ConnectionRefused {
+                    return Self::ConnectionRefused;
+                }
+            }
+            source = cause.source();
+        }
+        if error.is_connect() && error.to_string().contains("refused") {
+            return Self::ConnectionRefused;
+        }
+        Self::Transport
+    }
+}
+
+impl std::fmt::Display for HealthFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ConnectionRefused => "connection refused",
+            Self::Timeout => "timed out",
+            Self::Status => "error status",
+            Self::Transport => "transport error",
+        })
+    }
+}
+
+/// Why one spawn attempt failed
+enum SpawnError {
+    /// The port was taken or the sidecar never became healthy; try a fresh port
+    Retryable(String),
+    /// Spawning or the sidecar itself is broken; another port won't help
+    Fatal(String),
+    /// The overall readiness deadline passed, with why the last check failed
+    DeadlineExceeded(Option<HealthFailure>),
+}
+
+/// A sidecar that passed its startup health check
+struct SpawnedSidecar {
+    /// `None` for a manual (LIGHTBOT_SIDECAR_PORT) sidecar
+    child: Option<Child>,
+    port: u16,
+    health: Option<HealthInfo>,
+    metrics: StartupMetrics,
+}
+
+/// How long each phase of the last successful sidecar start took, in milliseconds.
+/// Phases that didn't run (e.g. resolution for a manual sidecar) are `None`.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StartupMetrics {
+    /// Searching `possible_paths` for the binary
+    pub resolve_ms: Option<u64>,
+    /// `Command::spawn` for the attempt that succeeded
+    pub spawn_ms: Option<u64>,
+    /// From spawn (or from connecting, for a manual sidecar) to the first healthy /health
+    pub first_health_ms: Option<u64>,
+    /// Round trip of that healthy /health request alone
+    pub health_latency_ms: Option<u64>,
+    /// The whole start, including failed attempts
+    pub total_ms: u64,
+    /// The candidate path that was used
+    pub matched_path: Option<String>,
+    /// Index of `matched_path` in the candidate list
+    pub matched_index: Option<usize>,
+    pub attempts: u32,
+}
+
+fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+/// Lifecycle of the Python sidecar, emitted to the frontend as `sidecar-status`
+#[derive(Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SidecarStatus {
+    Starting,
+    Ready { port: u16 },
+    Failed {
+        error: String,
+        /// How the process ended, when it died after starting
+        exit: Option<SidecarExit>,
+    },
+}
+
+/// How the sidecar process ended
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SidecarExit {
+    /// Exited with status 0
+    Clean,
+    /// Exited with a non-zero status
+    Code { code: i32 },
+    /// Killed by a signal (Unix only), e.g. 9 for an OOM kill
+    Signal { signal: i32 },
+    /// No code or signal reported
+    Unknown,
+}
+
+impl SidecarExit {
+    fn from_status(status: ExitStatus) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                return Self::Signal { signal };
+            }
+        }
+        match status.code() {
+            Some(0) => Self::Clean,
+            Some(code) => Self::Code { code },
+            None => Self::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for SidecarExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Clean => write!(f, "exited cleanly (code 0)"),
+            Self::Code { code } => write!(f, "exited with code {}", code),
+            Self::Signal { signal } => write!(f, "was killed by signal {}", signal),
+            Self::Unknown => write!(f, "exited with unknown status"),
+        }
+    }
+}
+
+/// What happened to the sidecar, as recorded in its history
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SidecarEventKind {
+    /// A process was launched (each port attempt counts)
+    Spawn,
+    /// A start passed its health check
+    Ready,
+    /// A start gave up
+    Failed,
+    /// The process died after it became ready
+    Crash,
+    /// A restart was requested (command, hotkey or watchdog)
+    Restart,
+    /// The process was stopped because the app is exiting
+    Stop,
+    /// The process was left running because the app is exiting with KEEP_SIDECAR_ON_EXIT
+    Detach,
+    /// A process left running by a previous launch was taken over
+    Adopt,
+    /// An external sidecar still answering on the last port was reused
+    Reconnect,
+}
+
+/// One entry of `get_sidecar_history`, also appended to sidecar-events.jsonl
+#[derive(Clone, Debug, Serialize)]
+pub struct SidecarEvent {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    pub kind: SidecarEventKind,
+    pub port: Option<u16>,
+    pub exit: Option<SidecarExit>,
+    pub error: Option<String>,
+}
+
+/// Where a sidecar left running on exit is recorded for the next launch
+#[derive(Debug, Serialize, Deserialize)]
+struct DetachedSidecar {
+    pid: u32,
+    port: u16,
+}
+
+/// Name of the detached-sidecar lockfile in ~/.lightbot
+const DETACHED_LOCK_FILE: &str = "sidecar.lock";
+
+/// The last healthy sidecar, saved for `reconnect_last_port`
+#[derive(Debug, Serialize, Deserialize)]
+struct LastSidecar {
+    port: u16,
+    /// PID of the process, when the app spawned or adopted it
+    pid: Option<u32>,
+    /// Whether the app owned it, so an orphan left by a crashed launch can be
+    /// adopted rather than treated as external
+    owned: bool,
+}
+
+/// Name of the file in ~/.lightbot holding the last healthy sidecar
+const LAST_PORT_FILE: &str = "sidecar.port";
+
+/// What the sidecar's /health reports as `app`, to tell it apart from any
+/// other server that happens to answer on a remembered port
+const SIDECAR_APP_ID: &str = "lightbot-sidecar";
+
+/// How long the saved port gets to answer before a new sidecar is spawned
+const LAST_PORT_PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How many lifecycle events are kept in memory
+const HISTORY_CAPACITY: usize = 100;
+
+/// File under the log directory that every lifecycle event is appended to
+const HISTORY_FILE: &str = "sidecar-events.jsonl";
+
+/// The shared client's default for requests that don't set their own
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a proxied request may take unless the call or
+/// SIDECAR_REQUEST_TIMEOUT_MS says otherwise; generous since inference is slow
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 30_000;
+
+/// How long the shared client waits for a connection to the sidecar
+const CLIENT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long `sidecar_request` waits for a starting sidecar unless the caller says otherwise
+const DEFAULT_PROXY_READY_WAIT: Duration = Duration::from_secs(30);
+
+/// Shown by the UI when the sidecar binary is missing
+const SIDECAR_BUILD_HINT: &str = "Run ./scripts/build-sidecar.sh from the project root";
+
+/// Sidecar binary name without the target-triple suffix (SIDECAR_BINARY_STEM)
+const DEFAULT_BINARY_STEM: &str = "python-sidecar";
+
+const DEFAULT_SIDECAR_HOST: &str = "127.0.0.1";
+const DEFAULT_HEALTH_PATH: &str = "/health";
+const DEFAULT_RELOAD_PATH: &str = "/reload";
+
+/// Env vars with this prefix are forwarded to the sidecar with the prefix stripped
+pub(crate) const SIDECAR_ENV_PREFIX: &str = "SIDECAR_ENV_";
+
+/// How often the exit watcher polls the sidecar process
+const EXIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Minimum gap between two "backend stopped" notifications
+const FAILURE_NOTICE_INTERVAL: Duration = Duration::from_secs(300);
+
+const DEFAULT_WATCHDOG_INTERVAL_SECS: u64 = 10;
+const DEFAULT_WATCHDOG_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_WATCHDOG_FAILURES: u32 = 3;
+
+/// How many ports to try before giving up on starting the sidecar
+const SPAWN_ATTEMPTS: u32 = 3;
+
+/// How long a start may take across all attempts before it's abandoned
+const DEFAULT_READY_TIMEOUT_SECS: u64 = 120;
+
+/// Timeout for an on-demand health check
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Timeout for the sidecar to re-read its config
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the sidecar gets to exit after SIGTERM before it's killed
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Status and body of a request forwarded by `sidecar_request`
+#[derive(Clone, Serialize)]
+pub struct SidecarResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Returned by `get_full_status`: the latest of each lifecycle event's
+/// payload, so the frontend can catch up on mount
+#[derive(Clone, Serialize)]
+pub struct SidecarFullStatus {
+    /// As last sent in `sidecar-status`
+    pub status: SidecarStatus,
+    /// 0 until the sidecar is ready
+    pub port: u16,
+    /// As sent in `sidecar-ready`
+    pub health: Option<HealthInfo>,
+    /// As last sent in `sidecar-progress`
+    pub progress: Option<f64>,
+    /// As last sent in `sidecar-error`
+    pub error_detail: Option<SidecarStartError>,
+    pub managed: bool,
+}
+
+/// Snapshot returned by `get_sidecar_status`: `port` is 0 until the sidecar
+/// is ready, and `error` is only set once a start has failed
+#[derive(Clone, Serialize)]
+pub struct SidecarStatusReport {
+    pub port: u16,
+    pub error: Option<String>,
+    pub starting: bool,
+    /// False for an external sidecar the app won't restart or stop
+    pub managed: bool,
+}
+
+/// Result of a live `/health` request from `check_sidecar_health`
+#[derive(Clone, Serialize)]
+pub struct HealthReport {
+    pub port: u16,
+    /// Whether /health answered with a 2xx status
+    pub healthy: bool,
+    pub status: u16,
+    pub latency_ms: u64,
+    /// The parsed body, or `None` if it was empty or not JSON
+    pub health: Option<HealthInfo>,
+}
+
+/// Why `sidecar_request` couldn't get a response from the sidecar.
+/// HTTP error statuses are not errors; they come back as a `SidecarResponse`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum SidecarError {
+    /// The sidecar hasn't started, or failed to
+    NotReady(String),
+    /// The sidecar was still starting when the wait for it ran out
+    HealthTimeout(String),
+    /// Bad method or path from the caller
+    InvalidRequest(String),
+    /// The sidecar didn't answer within the timeout; worth retrying
+    RequestTimeout(String),
+    /// Connection or transport failure
+    Request(String),
+}
+
+impl std::fmt::Display for SidecarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotReady(msg) => write!(f, "Sidecar not ready: {}", msg),
+            Self::HealthTimeout(msg) => write!(f, "Sidecar still starting: {}", msg),
+            Self::InvalidRequest(msg) => write!(f, "Invalid sidecar request: {}", msg),
+            Self::RequestTimeout(msg) => write!(f, "Sidecar request timed out: {}", msg),
+            Self::Request(msg) => write!(f, "Sidecar request failed: {}", msg),
+        }
+    }
+}
+
+/// Add a lifecycle event to the history and append it to sidecar-events.jsonl
+fn record_event(
+    state: &SidecarState,
+    kind: SidecarEventKind,
+    port: Option<u16>,
+    exit: Option<SidecarExit>,
+    error: Option<&str>,
+) {
+    let event = SidecarEvent {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or_default(),
+        kind,
+        port,
+        exit,
+        error: error.map(|e| redact::redact(e).into_owned()),
+    };
+    log::debug!("Sidecar event: {:?}", event);
+    append_history_line(&event);
+
+    let mut history = lock(&state.history);
+    if history.len() == HISTORY_CAPACITY {
+        history.pop_front();
+    }
+    history.push_back(event);
+}
+
+/// Best effort: the in-memory history is still kept if the file can't be written
+fn append_history_line(event: &SidecarEvent) {
+    let path = logging::resolve_log_dir().join(HISTORY_FILE);
+    let line = match serde_json::to_string(event) {
+        Ok(line) => line,
+        Err(e) => {
+            log::warn!("Failed to serialize sidecar event: {}", e);
+            return;
+        }
+    };
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = result {
+        log::warn!("Failed to append to {:?}: {}", path, e);
+    }
+}
+
+/// Publish a sidecar status transition to the tray and the frontend
+fn set_sidecar_status<R: Runtime>(app: &tauri::AppHandle<R>, status: SidecarStatus) {
+    tray::update_tray_status(app, &status);
+    app.state::<SidecarState>().set_status(status.clone());
+
+    if let Err(e) = app.emit("sidecar-status", status) {
+        log::error!("Failed to emit sidecar-status event: {}", e);
+    }
+}
+
+/// Readiness deadline from SIDECAR_READY_TIMEOUT_SECS, or the default
+fn ready_timeout_from_env() -> Duration {
+    let secs = match std::env::var("SIDECAR_READY_TIMEOUT_SECS") {
+        Ok(raw) => match raw.trim().parse::<u64>() {
+            Ok(secs) if secs > 0 => secs,
+            _ => {
+                log::warn!(
+                    "Invalid SIDECAR_READY_TIMEOUT_SECS '{}', using {}s",
+                    raw,
+                    DEFAULT_READY_TIMEOUT_SECS
+                );
+                DEFAULT_READY_TIMEOUT_SECS
+            }
+        },
+        Err(_) => DEFAULT_READY_TIMEOUT_SECS,
+    };
+    Duration::from_secs(secs)
+}
+
+async fn spawn_python_sidecar<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+) -> Result<SpawnedSidecar, SidecarStartError> {
+    let started = Instant::now();
+    let ready_timeout = ready_timeout_from_env();
+    let deadline = started + ready_timeout;
+    let timed_out = |last_failure: Option<HealthFailure>| SidecarStartError::HealthTimeout {
+        message: match last_failure {
+            Some(reason) => format!(
+                "Sidecar did not become healthy within {}s (last check: {})",
+                ready_timeout.as_secs(),
+                reason
+            ),
+            None => format!("Sidecar did not become healthy within {}s", ready_timeout.as_secs()),
+        },
+        timeout_secs: ready_timeout.as_secs(),
+        last_failure,
+    };
+
+    let endpoint = SidecarEndpoint::from_env()?;
+    let client = http_client_from_env()?;
+    app.state::<SidecarState>().set_connection(endpoint.clone(), client.clone());
+
+    // Check for manual sidecar port (Option 1 - Manual Dev Mode)
+    if let Some(port) = manual_port() {
+        log::info!("🚀 Manual sidecar mode: using the Python sidecar already on port {}", port);
+        
+        // Verify the manual server is actually responsive
+        let health_url = endpoint.health_url(port);
+        
+        let request_started = Instant::now();
+        match client.get(&health_url).timeout(std::time::Duration::from_secs(2)).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                log::info!("Verified manual sidecar is healthy on port {}", port);
+                let health_latency_ms = elapsed_ms(request_started);
+                let health = HealthInfo::parse(&resp.text().await.unwrap_or_default());
+                let metrics = StartupMetrics {
+                    first_health_ms: Some(elapsed_ms(started)),
+                    health_latency_ms: Some(health_latency_ms),
+                    total_ms: elapsed_ms(started),
+                    attempts: 1,
+                    ..Default::default()
+                };
+                return Ok(SpawnedSidecar { child: None, port, health, metrics });
+            }
+            _ => {
+                return Err(format!("Manual sidecar port {} provided via --sidecar-port or LIGHTBOT_SIDECAR_PORT but server is not responding at {}", port, health_url).into());
+            }
+        }
+    }
+
+    // An explicit port from set_sidecar_port always gets a fresh sidecar
+    let required_port = lock(&app.state::<SidecarState>().requested_port).take();
+    if required_port.is_none() {
+        if let Some(adopted) = adopt_detached_sidecar(app, &client, &endpoint, started).await {
+            return Ok(adopted);
+        }
+        if let Some(reconnected) = reconnect_last_port(app, &client, &endpoint, started).await {
+            return Ok(reconnected);
+        }
+    }
+
+    log::info!("Managed sidecar mode: spawning the Python sidecar");
+    let stem = binary_stem();
+    let [sidecar_with_triple, _] = binary_names(&stem, &sidecar_triple());
+
+    let current_exe = std::env::current_exe().ok();
+    let exe_dir = current_exe.as_ref().and_then(|p| p.parent().map(|p| p.to_path_buf()));
+    let search_paths = sidecar_search_paths(app, &stem, &sidecar_triple());
+
+    // Log debugging info
+    log::debug!("Current exe: {:?}", current_exe);
+    log::debug!("Looking for sidecar: {}", sidecar_with_triple);
+
+    // Additional debugging - check if we're in /Applications
+    if let Ok(exe) = std::env::current_exe() {
+        let exe_str = exe.display().to_string();
+        if exe_str.contains("/Applications/") {
+            log::debug!("Running from /Applications (bundled app)");
+        } else if exe_str.contains("target/") {
+            log::debug!("Running from target/ (development build)");
+        } else {
+            log::debug!("Running from unknown location: {}", exe_str);
+        }
+    }
+    
+    let resolve_started = Instant::now();
+    let matched = find_sidecar(&search_paths);
+    let resolve_ms = elapsed_ms(resolve_started);
+    let matched_index = matched.as_ref().map(|(index, _)| *index);
+    log::debug!("Checked sidecar paths: {:?}", search_paths);
+    match &matched {
+        Some((index, path)) => log::debug!("Matched sidecar path #{}: {:?}", index, path),
+        None => log::debug!("No sidecar path matched"),
+    }
+
+    let sidecar_path = matched.map(|(_, path)| path).ok_or_else(|| {
+        let checked_paths: Vec<String> = search_paths.iter().map(|p| p.display().to_string()).collect();
+        // Dev builds run the binary from src-tauri/bin; bundles ship it next to the app executable
+        let expected_path = if let Some(path) = binary_path_override() {
+            Some(path)
+        } else if cfg!(debug_assertions) {
+            Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("bin").join(&sidecar_with_triple))
+        } else {
+            exe_dir.as_ref().map(|d| d.join(&sidecar_with_triple))
+        };
+        SidecarStartError::BinaryNotFound {
+            message: format!("Python sidecar binary not found. Checked paths: {:?}", checked_paths),
+            expected_path: expected_path.map(|p| p.display().to_string()).unwrap_or(sidecar_with_triple.clone()),
+            tried_paths: checked_paths,
+            build_hint: SIDECAR_BUILD_HINT.to_string(),
+        }
+    })?;
+
+    // The dev fallbacks are relative to our working directory, not the sidecar's
+    let sidecar_path = std::fs::canonicalize(&sidecar_path).unwrap_or(sidecar_path);
+    let working_dir = sidecar_working_dir();
+    match &working_dir {
+        Some(dir) => log::info!("Sidecar working directory: {:?}", dir),
+        None => log::info!("Sidecar working directory: {:?} (inherited)", std::env::current_dir().ok()),
+    }
+
+    // A port that was free when picked can be taken before the sidecar binds
+    // it, so retry bind failures and hung startups on a fresh port
+    let preferred_port = app.state::<ConfigState>().config.lock().unwrap().preferred_port;
+    let mut last_error = String::new();
+    for attempt in 1..=SPAWN_ATTEMPTS {
+        if Instant::now() >= deadline {
+            return Err(timed_out(None));
+        }
+
+        let port = match (required_port, preferred_port) {
+            (Some(port), _) => port,
+            (None, Some(port)) if attempt == 1 && portpicker::is_free(port) => port,
+            (None, Some(port)) if attempt == 1 => {
+                log::warn!("Preferred sidecar port {} is in use, picking another", port);
+                portpicker::pick_unused_port().ok_or("No available port")?
+            }
+            _ => portpicker::pick_unused_port().ok_or("No available port")?,
+        };
+
+        match spawn_on_port(app, &sidecar_path, working_dir.as_deref(), &endpoint, port, deadline).await {
+            Ok(mut spawned) => {
+                spawned.metrics.resolve_ms = Some(resolve_ms);
+                spawned.metrics.total_ms = elapsed_ms(started);
+                spawned.metrics.matched_path = Some(sidecar_path.display().to_string());
+                spawned.metrics.matched_index = matched_index;
+                spawned.metrics.attempts = attempt;
+                return Ok(spawned);
+            }
+            Err(SpawnError::Retryable(e)) if required_port.is_some() => {
+                return Err(format!("{} (port {} was requested, not trying another)", e, port).into());
+            }
+            Err(SpawnError::Retryable(e)) => {
+                log::warn!("Sidecar attempt {}/{} on port {} failed: {}", attempt, SPAWN_ATTEMPTS, port, e);
+                last_error = e;
+            }
+            Err(SpawnError::Fatal(e)) => {
+                return Err(format!("{} (attempt {}/{})", e, attempt, SPAWN_ATTEMPTS).into());
+            }
+            Err(SpawnError::DeadlineExceeded(last_failure)) => return Err(timed_out(last_failure)),
+        }
+    }
+
+    Err(format!("{} (gave up after {} attempts)", last_error, SPAWN_ATTEMPTS).into())
+}
+
+/// Port of an already running sidecar: `--sidecar-port`, then LIGHTBOT_SIDECAR_PORT
+fn manual_port() -> Option<u16> {
+    if let Some(port) = crate::cli::args().sidecar_port {
+        return Some(port);
+    }
+    let raw = std::env::var("LIGHTBOT_SIDECAR_PORT").ok()?;
+    parse_manual_port(&raw).unwrap_or_else(|e| {
+        log::warn!("Ignoring LIGHTBOT_SIDECAR_PORT: {}", e);
+        None
+    })
+}
+
+/// Parse a LIGHTBOT_SIDECAR_PORT value. Whitespace and surrounding quotes are
+/// stripped, and an empty value counts as unset.
+fn parse_manual_port(raw: &str) -> Result<Option<u16>, String> {
+    let value = raw.trim().trim_matches(|c| c == '\'' || c == '"').trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(Some(port)),
+        _ => Err(format!("'{}' is not a valid port", raw)),
+    }
+}
+
+/// Target triple the bundled sidecar binary is suffixed with
+fn sidecar_triple() -> String {
+    let arch = if cfg!(target_arch = "aarch64") { "aarch64" } else { "x86_64" };
+    format!("{}-apple-darwin", arch)
+}
+
+/// SIDECAR_BINARY_STEM, for a renamed or alternate build of the sidecar
+/// (e.g. `python-sidecar-dev`). A value with a path separator is ignored;
+/// SIDECAR_BINARY_PATH is the way to point somewhere else.
+fn binary_stem() -> String {
+    match std::env::var("SIDECAR_BINARY_STEM") {
+        Ok(raw) if !raw.trim().is_empty() => {
+            let stem = raw.trim();
+            if stem.contains(['/', '\\']) {
+                log::warn!("Invalid SIDECAR_BINARY_STEM '{}', using {}", raw, DEFAULT_BINARY_STEM);
+                DEFAULT_BINARY_STEM.to_string()
+            } else {
+                stem.to_string()
+            }
+        }
+        _ => DEFAULT_BINARY_STEM.to_string(),
+    }
+}
+
+/// File names tried for `stem`: triple-suffixed first, then plain
+fn binary_names(stem: &str, triple: &str) -> [String; 2] {
+    [format!("{}-{}", stem, triple), stem.to_string()]
+}
+
+/// Every path checked for the sidecar binary, in order
+pub fn resolved_search_paths<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<PathBuf> {
+    sidecar_search_paths(app, &binary_stem(), &sidecar_triple())
+}
+
+/// SIDECAR_BINARY_PATH, for a binary outside the usual locations
+fn binary_path_override() -> Option<PathBuf> {
+    std::env::var_os("SIDECAR_BINARY_PATH")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Where to look for the sidecar binary named `stem` for `triple`, in order.
+/// Only SIDECAR_BINARY_PATH is searched when it's set.
+fn sidecar_search_paths<R: Runtime>(app: &tauri::AppHandle<R>, stem: &str, triple: &str) -> Vec<PathBuf> {
+    let names = binary_names(stem, triple);
+    let exe_dir = std::env::current_exe().ok().and_then(|p| p.parent().map(|p| p.to_path_buf()));
+    let resolve = |path: String, base: tauri::path::BaseDirectory| app.path().resolve(path, base).ok();
+
+    let mut candidates = Vec::new();
+    for name in &names {
+        // Bundled app: sidecar is in same directory as main executable (Contents/MacOS/)
+        candidates.push(exe_dir.as_ref().map(|d| d.join(name)));
+    }
+    for name in &names {
+        // Bundled paths via Resource (for older Tauri versions)
+        candidates.push(resolve(format!("bin/{}", name), tauri::path::BaseDirectory::Resource));
+    }
+    for name in &names {
+        // Development paths (relative to app directory)
+        candidates.push(resolve(format!("src-tauri/bin/{}", name), tauri::path::BaseDirectory::AppConfig));
+    }
+    // Direct relative paths as fallback for dev
+    for dir in ["src-tauri/bin", "bin"] {
+        for name in &names {
+            candidates.push(Some(Path::new(dir).join(name)));
+        }
+    }
+
+    order_search_paths(binary_path_override(), candidates)
+}
+
+/// The override alone if there is one, otherwise the resolved candidates
+/// in order with duplicates dropped
+fn order_search_paths(
+    override_path: Option<PathBuf>,
+    candidates: impl IntoIterator<Item = Option<PathBuf>>,
+) -> Vec<PathBuf> {
+    if let Some(path) = override_path {
+        log::info!("Using sidecar from SIDECAR_BINARY_PATH: {:?}", path);
+        return vec![path];
+    }
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for path in candidates.into_iter().flatten() {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// The first candidate that exists, with its index in `candidates`
+fn find_sidecar(candidates: &[PathBuf]) -> Option<(usize, PathBuf)> {
+    candidates
+        .iter()
+        .enumerate()
+        .find(|(_, path)| path.exists())
+        .map(|(index, path)| (index, path.clone()))
+}
+
+/// Working directory for the sidecar: SIDECAR_CWD, or ~/.lightbot. A bundled
+/// app's own working directory is `/`, so relative paths in the sidecar need a
+/// stable base. Created if missing; `None` (inherit ours) if that fails.
+fn sidecar_working_dir() -> Option<PathBuf> {
+    let dir = match std::env::var("SIDECAR_CWD").map(|d| d.trim().to_string()) {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::home_dir()?.join(".lightbot"),
+    };
+    match std::fs::create_dir_all(&dir) {
+        Ok(()) => Some(dir),
+        Err(e) => {
+            log::warn!("Failed to create sidecar working directory {:?}: {}", dir, e);
+            None
+        }
+    }
+}
+
+/// Log level to start the sidecar with: SIDECAR_LOG_LEVEL if set, otherwise
+/// the app's current level translated to the sidecar's names
+fn sidecar_log_level<R: Runtime>(app: &tauri::AppHandle<R>) -> String {
+    if let Some(level) = log_level_override() {
+        return level;
+    }
+    let app_level = app.state::<logging::LoggerState>().level.lock().unwrap().clone();
+    logging::sidecar_log_level(&app_level).to_string()
+}
+
+/// Levels the sidecar's `--log-level` accepts
+const SIDECAR_LOG_LEVELS: &[&str] = &["critical", "error", "warning", "info", "debug"];
+
+fn log_level_override() -> Option<String> {
+    let level = std::env::var("SIDECAR_LOG_LEVEL").ok()?.trim().to_ascii_lowercase();
+    if level.is_empty() {
+        return None;
+    }
+    if !SIDECAR_LOG_LEVELS.contains(&level.as_str()) {
+        log::warn!("Ignoring SIDECAR_LOG_LEVEL '{}', expected one of {}", level, SIDECAR_LOG_LEVELS.join(", "));
+        return None;
+    }
+    Some(level)
+}
+
+/// Pass an app log level change on to the running sidecar, unless
+/// SIDECAR_LOG_LEVEL pins it. Best effort: failures are only logged.
+pub fn sync_log_level<R: Runtime>(app: &tauri::AppHandle<R>, app_level: &str) {
+    if log_level_override().is_some() {
+        return;
+    }
+    let state = app.state::<SidecarState>();
+    let port = state.get_port();
+    if port == 0 {
+        return;
+    }
+
+    let url = format!("{}/log-level", state.endpoint().base_url(port));
+    let level = logging::sidecar_log_level(app_level);
+    let client = state.client();
+    tauri::async_runtime::spawn(async move {
+        let result = client
+            .post(&url)
+            .json(&serde_json::json!({ "level": level }))
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => {
+                let body = resp.text().await.unwrap_or_default();
+                match log_level_rejection(&body) {
+                    Some(reason) => log::warn!("Sidecar rejected log level {}: {}", level, reason),
+                    None => log::info!("Set sidecar log level to {}", level),
+                }
+            }
+            Ok(resp) => log::warn!("Sidecar rejected log level {}: status {}", level, resp.status()),
+            Err(e) => log::warn!("Failed to set sidecar log level: {}", e),
+        }
+    });
+}
+
+/// Why a `/log-level` reply reports failure despite its 2xx status, if it
+/// does: `{"status": "error", "message": ...}`
+fn log_level_rejection(body: &str) -> Option<String> {
+    let reply = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    if reply.get("status").and_then(|s| s.as_str()) != Some("error") {
+        return None;
+    }
+    let message = reply.get("message").and_then(|m| m.as_str()).unwrap_or("no reason given");
+    Some(message.to_string())
+}
+
+/// Extra sidecar arguments from SIDECAR_EXTRA_ARGS, split with shell quoting rules
+fn extra_args_from_env() -> Vec<String> {
+    let Ok(raw) = std::env::var("SIDECAR_EXTRA_ARGS") else {
+        return Vec::new();
+    };
+    shlex::split(&raw).unwrap_or_else(|| {
+        log::warn!("Ignoring SIDECAR_EXTRA_ARGS: unbalanced quotes in '{}'", redact::redact(&raw));
+        Vec::new()
+    })
+}
+
+/// Wrapper the sidecar is launched through, from SIDECAR_LAUNCHER (e.g.
+/// `lldb --` or `py-spy record -o out.svg --`): the program and its leading
+/// args, with the sidecar path and its own args appended. Empty when unset.
+fn launcher_from_env() -> Vec<String> {
+    let Ok(raw) = std::env::var("SIDECAR_LAUNCHER") else {
+        return Vec::new();
+    };
+    shlex::split(&raw).unwrap_or_else(|| {
+        log::warn!("Ignoring SIDECAR_LAUNCHER: unbalanced quotes in '{}'", redact::redact(&raw));
+        Vec::new()
+    })
+}
+
+/// Extra env vars for the sidecar only: every `SIDECAR_ENV_<NAME>` is passed
+/// as `<NAME>`, then the entries of SIDECAR_ENV_FILE (dotenv format) on top.
+/// The sidecar also inherits the app's own environment.
+fn extra_env_from_env() -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| key != "SIDECAR_ENV_FILE")
+        .filter_map(|(key, value)| {
+            let name = key.strip_prefix(SIDECAR_ENV_PREFIX)?;
+            (!name.is_empty()).then(|| (name.to_string(), value))
+        })
+        .collect();
+
+    if let Ok(path) = std::env::var("SIDECAR_ENV_FILE") {
+        match dotenvy::from_path_iter(&path) {
+            Ok(entries) => {
+                for entry in entries {
+                    match entry {
+                        Ok(pair) => vars.push(pair),
+                        Err(e) => log::warn!("Skipping invalid line in SIDECAR_ENV_FILE {}: {}", path, e),
+                    }
+                }
+            }
+            Err(e) => log::warn!("Failed to read SIDECAR_ENV_FILE {}: {}", path, e),
+        }
+    }
+
+    vars
+}
+
+/// Whether a line of sidecar output reports that its port was already taken
+fn is_bind_error(line: &str) -> bool {
+    let line = line.to_ascii_lowercase();
+    line.contains("address already in use") || line.contains("only one usage of each socket address")
+}
+
+/// Kill a sidecar that never became healthy
+fn kill_child(mut child: Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Spawn the sidecar on `port` and wait for its first successful health
+/// check, killing it if `deadline` passes first
+async fn spawn_on_port<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    sidecar_path: &Path,
+    working_dir: Option<&Path>,
+    endpoint: &SidecarEndpoint,
+    port: u16,
+    deadline: Instant,
+) -> Result<SpawnedSidecar, SpawnError> {
+    log::info!("Spawning Python sidecar from: {:?} on port {}", sidecar_path, port);
+
+    // Extra args come last so they can still override the log level
+    let extra_args: Vec<String> = ["--log-level".to_string(), sidecar_log_level(app)]
+        .into_iter()
+        .chain(extra_args_from_env())
+        .collect();
+    let extra_env = extra_env_from_env();
+    let launcher = launcher_from_env();
+    if !launcher.is_empty() {
+        log::warn!("Launching the sidecar through SIDECAR_LAUNCHER: {}", redact::redact(&launcher.join(" ")));
+    }
+
+    // Log the argv so a start can be reproduced by hand
+    let argv: Vec<String> = launcher
+        .iter()
+        .cloned()
+        .chain(std::iter::once(sidecar_path.display().to_string()))
+        .chain(["--port".to_string(), port.to_string()])
+        .chain(extra_args.iter().cloned())
+        .collect();
+    let command_line = shlex::try_join(argv.iter().map(String::as_str)).unwrap_or_else(|_| argv.join(" "));
+    log::info!("Sidecar command: {}", redact::redact(&command_line));
+    if !extra_env.is_empty() {
+        let keys: Vec<&str> = extra_env.iter().map(|(key, _)| key.as_str()).collect();
+        log::info!("Sidecar extra env: {}", keys.join(", "));
+    }
+
+    // Send sidecar output to its own log file rather than interleaving it with ours
+    let sidecar_writer = app.state::<logging::LoggerState>().sidecar_writer.clone();
+
+    let spawn_started = Instant::now();
+    let mut child = spawn_child(&launcher, sidecar_path, working_dir, port, &extra_args, &extra_env, sidecar_writer.is_some())?;
+    record_event(&app.state::<SidecarState>(), SidecarEventKind::Spawn, Some(port), None, None);
+    let spawn_ms = elapsed_ms(spawn_started);
+
+    // Bind failures can only be told apart from other crashes by the output,
+    // so without a sidecar log every early exit counts as a crash
+    let bind_failed = Arc::new(AtomicBool::new(false));
+    if let Some(writer) = sidecar_writer {
+        let watch = |flag: Arc<AtomicBool>| {
+            move |line: &str| {
+                if is_bind_error(line) {
+                    flag.store(true, Ordering::SeqCst);
+                }
+            }
+        };
+        if let Some(stdout) = child.stdout.take() {
+            logging::pipe_to_sidecar_log(stdout, writer.clone(), watch(bind_failed.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            logging::pipe_to_sidecar_log(stderr, writer, watch(bind_failed.clone()));
+        }
+    }
+
+    // Wait until the server is ready, not just listening
+    let client = app.state::<SidecarState>().client();
+    let ready_url = endpoint.ready_url(port);
+    let ready = wait_until_ready(&client, &ready_url, child, &bind_failed, deadline, &startup_poll(), |health| {
+        emit_progress(app, health)
+    })
+    .await?;
+
+    let metrics = StartupMetrics {
+        spawn_ms: Some(spawn_ms),
+        first_health_ms: Some(elapsed_ms(spawn_started)),
+        health_latency_ms: Some(ready.latency_ms),
+        ..Default::default()
+    };
+    Ok(SpawnedSidecar { child: Some(ready.child), port, health: ready.health, metrics })
+}
+
+/// Start the sidecar binary on `port`, through `launcher` when it's not
+/// empty (the child is then the launcher). Output is piped when
+/// `pipe_output` is set, otherwise inherited.
+fn spawn_child(
+    launcher: &[String],
+    sidecar_path: &Path,
+    working_dir: Option<&Path>,
+    port: u16,
+    extra_args: &[String],
+    extra_env: &[(String, String)],
+    pipe_output: bool,
+) -> Result<Child, SpawnError> {
+    let mut command = match launcher.split_first() {
+        Some((program, args)) => {
+            let mut command = std::process::Command::new(program);
+            command.args(args).arg(sidecar_path);
+            command
+        }
+        None => std::process::Command::new(sidecar_path),
+    };
+    command.arg("--port").arg(port.to_string());
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+    command.args(extra_args);
+
+    #[cfg(target_os = "macos")]
+    {
+        command.env("PYTHONUNBUFFERED", "1");
+    }
+
+    command.envs(extra_env.iter().map(|(key, value)| (key, value)));
+    if pipe_output {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+
+    match command.spawn() {
+        Ok(child) => {
+            log::info!("Sidecar process spawned with PID {}", child.id());
+            Ok(child)
+        }
+        Err(e) => {
+            let err = format!("Failed to spawn sidecar: {}", e);
+            log::error!("{}", err);
+            Err(SpawnError::Fatal(err))
+        }
+    }
+}
+
+/// Timing of the startup readiness poll
+struct ReadinessPoll {
+    /// Before the first request (SIDECAR_INITIAL_DELAY_MS)
+    initial_delay: Duration,
+    /// Gap between requests for the first `fast_window`, so a quick start is seen quickly
+    fast_interval: Duration,
+    fast_window: Duration,
+    interval: Duration,
+    /// Failed requests allowed before giving up on this port, not counting
+    /// those in the fast window
+    retries: u32,
+    request_timeout: Duration,
+}
+
+const DEFAULT_INITIAL_DELAY_MS: u64 = 0;
+
+/// The startup poll, with the initial delay from SIDECAR_INITIAL_DELAY_MS
+/// for sidecars known to bind slowly
+fn startup_poll() -> ReadinessPoll {
+    ReadinessPoll {
+        initial_delay: Duration::from_millis(env_number("SIDECAR_INITIAL_DELAY_MS", DEFAULT_INITIAL_DELAY_MS)),
+        fast_interval: Duration::from_millis(100),
+        fast_window: Duration::from_secs(1),
+        interval: Duration::from_millis(1000),
+        retries: 15,
+        request_timeout: Duration::from_secs(2),
+    }
+}
+
+/// A sidecar whose readiness check succeeded
+struct ReadySidecar {
+    child: Child,
+    health: Option<HealthInfo>,
+    /// Round trip of the successful request
+    latency_ms: u64,
+}
+
+/// Poll `url` until it answers with a success status. Fails early if `child`
+/// exits, and kills it once the retries or `deadline` run out. `on_poll` gets
+/// the parsed body of every answer (`None` if there was none).
+async fn wait_until_ready(
+    client: &reqwest::Client,
+    url: &str,
+    mut child: Child,
+    bind_failed: &AtomicBool,
+    deadline: Instant,
+    poll: &ReadinessPoll,
+    on_poll: impl Fn(Option<&HealthInfo>),
+) -> Result<ReadySidecar, SpawnError> {
+    tokio::time::sleep(poll.initial_delay).await;
+
+    let polling_started = Instant::now();
+    let mut retries = poll.retries;
+    let mut last_failure = None;
+    while retries > 0 {
+        if let Ok(Some(exit_status)) = child.try_wait() {
+            // Give the output threads a moment to see the final lines
+            tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+            return Err(if bind_failed.load(Ordering::SeqCst) {
+                SpawnError::Retryable("Sidecar could not bind its port".to_string())
+            } else {
+                SpawnError::Fatal(format!(
+                    "Sidecar {} before becoming healthy",
+                    SidecarExit::from_status(exit_status)
+                ))
+            });
+        }
+        if Instant::now() >= deadline {
+            log::warn!("Sidecar readiness deadline passed, killing PID {}", child.id());
+            kill_child(child);
+            return Err(SpawnError::DeadlineExceeded(last_failure));
+        }
+
+        // Failures while the sidecar is still booting are expected, so the
+        // fast window polls often and doesn't use up retries
+        let fast = polling_started.elapsed() < poll.fast_window;
+        let next_interval = if fast { poll.fast_interval } else { poll.interval };
+
+        let request_started = Instant::now();
+        match client.get(url).timeout(poll.request_timeout).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                log::info!("Python sidecar is healthy at {}", url);
+                let latency_ms = elapsed_ms(request_started);
+                let health = HealthInfo::parse(&resp.text().await.unwrap_or_default());
+                on_poll(health.as_ref());
+                return Ok(ReadySidecar { child, health, latency_ms });
+            }
+            Ok(resp) => {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                log::warn!("Health check returned status: {}, body: {}", status, body);
+                on_poll(HealthInfo::parse(&body).as_ref());
+                last_failure = Some(HealthFailure::Status);
+                if !fast {
+                    retries -= 1;
+                }
+                if retries == 0 {
+                    kill_child(child);
+                    let final_err = "Sidecar health check failed - /health not returning success".to_string();
+                    return Err(SpawnError::Retryable(final_err));
+                }
+                tokio::time::sleep(next_interval).await;
+            }
+            Err(e) => {
+                let reason = HealthFailure::classify(&e);
+                match reason {
+                    HealthFailure::ConnectionRefused => {
+                        log::debug!("Sidecar not accepting connections yet ({} retries left)", retries)
+                    }
+                    HealthFailure::Timeout => {
+                        log::warn!("Health check timed out, the sidecar may be wedged ({} retries left): {}", retries, e)
+                    }
+                    _ => log::debug!("Health check request failed, {} ({} retries left): {}", reason, retries, e),
+                }
+                on_poll(None);
+                last_failure = Some(reason);
+
+                if !fast {
+                    retries -= 1;
+                }
+                if retries == 0 {
+                    kill_child(child);
+                    let final_err = format!("Sidecar health check failed - server not responding (last check: {})", reason);
+                    return Err(SpawnError::Retryable(final_err));
+                }
+                tokio::time::sleep(next_interval).await;
+            }
+        }
+    }
+
+    kill_child(child);
+    Err(SpawnError::Fatal("Failed to start sidecar".to_string()))
+}
+
+/// Why the sidecar is being (re)started
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartCause {
+    /// The first start at launch
+    Launch,
+    /// The health watchdog restarting a wedged sidecar
+    Watchdog,
+    /// A command, hotkey or config reload
+    User,
+}
+
+impl StartCause {
+    /// Only automatic starts may end the app under QUIT_ON_SIDECAR_FAILURE;
+    /// a failed restart the user asked for is reported and the app stays up
+    fn may_quit(self) -> bool {
+        !matches!(self, StartCause::User)
+    }
+}
+
+/// Spawn (or connect to) the sidecar and publish the outcome to state, tray and frontend
+pub async fn start_sidecar<R: Runtime>(app_handle: &tauri::AppHandle<R>, cause: StartCause) {
+    let state = app_handle.state::<SidecarState>();
+    if state.starting.swap(true, Ordering::SeqCst) {
+        log::warn!("Sidecar is already starting, ignoring start request");
+        return;
+    }
+    set_sidecar_status(app_handle, SidecarStatus::Starting);
+    run_start(app_handle, cause).await;
+}
+
+/// The start itself, for a caller that has claimed `starting` and published
+/// Starting. Releases `starting` when done.
+async fn run_start<R: Runtime>(app_handle: &tauri::AppHandle<R>, cause: StartCause) {
+    let state = app_handle.state::<SidecarState>();
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    match spawn_python_sidecar(app_handle).await {
+        Ok(SpawnedSidecar { child, port, health, metrics }) => {
+            log::info!("Python sidecar started on port {}", port);
+            log::debug!("Sidecar startup metrics: {:?}", metrics);
+            *lock(&state.health_latency_ms) = metrics.health_latency_ms;
+            *lock(&state.metrics) = Some(metrics);
+            if health.is_none() {
+                log::debug!("Sidecar /health body was empty or not JSON");
+            }
+            
+            // Store the port and process in state
+            let owned_pid = child.as_ref().map(Child::id).or(*lock(&state.adopted_pid));
+            save_last_port(port, owned_pid);
+            state.set_port(port);
+            state.set_error(None);
+            let managed = owned_pid.is_some();
+            state.managed.store(managed, Ordering::SeqCst);
+            if !managed {
+                log::info!("Sidecar on port {} is external, it won't be restarted or stopped", port);
+            }
+            *lock(&state.child) = child;
+            *lock(&state.health) = health.clone();
+            *lock(&state.progress) = None;
+            *lock(&state.start_error) = None;
+            if managed {
+                watch_for_exit(app_handle, generation);
+            }
+            
+            // Emit event to frontend that sidecar is ready
+            if let Err(e) = app_handle.emit("sidecar-ready", SidecarReady { port, health }) {
+                log::error!("Failed to emit sidecar-ready event: {}", e);
+            }
+            record_event(&state, SidecarEventKind::Ready, Some(port), None, None);
+            set_sidecar_status(app_handle, SidecarStatus::Ready { port });
+        }
+        Err(e) => {
+            log::error!("Failed to start Python sidecar: {}", e);
+            
+            // Store the error in state
+            state.set_error(Some(e.message().to_string()));
+            *lock(&state.start_error) = Some(e.clone());
+            
+            if let Err(err) = app_handle.emit("sidecar-error", &e) {
+                log::error!("Failed to emit sidecar-error event: {}", err);
+            }
+            record_event(&state, SidecarEventKind::Failed, None, None, Some(e.message()));
+            set_sidecar_status(app_handle, SidecarStatus::Failed { error: e.message().to_string(), exit: None });
+            notify_failure(app_handle, e.message());
+            if cause.may_quit() {
+                shutdown::quit_on_sidecar_failure(app_handle, e.message());
+            }
+        }
+    }
+
+    state.starting.store(false, Ordering::SeqCst);
+}
+
+/// Health watchdog settings: a sidecar that's alive but wedged is restarted
+/// after `threshold` consecutive failed or slow health checks
+struct Watchdog {
+    interval: Duration,
+    /// A check slower than this counts as failed
+    timeout: Duration,
+    /// 0 disables the watchdog
+    threshold: u32,
+}
+
+impl Watchdog {
+    /// Read SIDECAR_WATCHDOG_INTERVAL_SECS, SIDECAR_WATCHDOG_TIMEOUT_MS and SIDECAR_WATCHDOG_FAILURES
+    fn from_env() -> Self {
+        Watchdog {
+            interval: Duration::from_secs(env_number("SIDECAR_WATCHDOG_INTERVAL_SECS", DEFAULT_WATCHDOG_INTERVAL_SECS)),
+            timeout: Duration::from_millis(env_number("SIDECAR_WATCHDOG_TIMEOUT_MS", DEFAULT_WATCHDOG_TIMEOUT_MS)),
+            threshold: env_number("SIDECAR_WATCHDOG_FAILURES", DEFAULT_WATCHDOG_FAILURES),
+        }
+    }
+}
+
+/// `key` parsed as a number, or `default` if it's unset or invalid
+fn env_number<T: std::str::FromStr + std::fmt::Display>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(raw) => raw.trim().parse().unwrap_or_else(|_| {
+            log::warn!("Invalid {} '{}', using {}", key, raw, default);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Payload of the `sidecar-unhealthy` event, sent just before the watchdog restarts the sidecar
+#[derive(Clone, Serialize)]
+struct SidecarUnhealthy {
+    failures: u32,
+    reason: HealthFailure,
+    error: String,
+}
+
+/// How many recent watchdog round trips the rolling average covers
+const LATENCY_WINDOW: usize = 10;
+
+/// Payload of the `sidecar-latency` event, sent after every successful watchdog check
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SidecarLatency {
+    pub last_ms: u64,
+    /// Mean of the last LATENCY_WINDOW samples
+    pub average_ms: u64,
+    pub samples: usize,
+}
+
+/// The most recent watchdog round trips
+#[derive(Default)]
+struct LatencyWindow {
+    samples: VecDeque<u64>,
+}
+
+impl LatencyWindow {
+    fn record(&mut self, ms: u64) -> SidecarLatency {
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ms);
+        SidecarLatency {
+            last_ms: ms,
+            average_ms: self.samples.iter().sum::<u64>() / self.samples.len() as u64,
+            samples: self.samples.len(),
+        }
+    }
+}
+
+/// One watchdog health check: `Ok` for a success status within `timeout`
+async fn probe_health(client: &reqwest::Client, url: &str, timeout: Duration) -> Result<(), (HealthFailure, String)> {
+    match client.get(url).timeout(timeout).send().await {
+        Ok(resp) if resp.status().is_success() => Ok(()),
+        Ok(resp) => Err((HealthFailure::Status, format!("status {}", resp.status()))),
+        Err(e) if e.is_timeout() => Err((HealthFailure::Timeout, format!("no answer within {}ms", timeout.as_millis()))),
+        Err(e) => Err((HealthFailure::classify(&e), e.to_string())),
+    }
+}
+
+/// Supervise the spawned or adopted sidecar. If it exits, log how it ended
+/// and publish the failure; if it stays alive but fails Watchdog::threshold
+/// health checks in a row, emit `sidecar-unhealthy` and restart it. Each
+/// successful check's round trip is published as `sidecar-latency`. An
+/// adopted sidecar has no `Child` to wait on, so its PID is polled instead.
+/// Stops quietly once the process is taken (stop or restart) or a newer
+/// start has begun.
+fn watch_for_exit<R: Runtime>(app_handle: &tauri::AppHandle<R>, generation: u64) {
+    let app_handle = app_handle.clone();
+    let watchdog = Watchdog::from_env();
+    tauri::async_runtime::spawn(async move {
+        let client = app_handle.state::<SidecarState>().client();
+        let mut last_check = Instant::now();
+        let mut failures = 0;
+        let mut latency = LatencyWindow::default();
+        loop {
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+            let state = app_handle.state::<SidecarState>();
+            if state.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            // `None` when there's no Child to poll, i.e. the sidecar was adopted
+            let polled = {
+                let mut child = lock(&state.child);
+                match child.as_mut().map(|process| process.try_wait()) {
+                    Some(Ok(Some(status))) => {
+                        child.take();
+                        Some(Some(SidecarExit::from_status(status)))
+                    }
+                    Some(Ok(None)) => Some(None),
+                    Some(Err(e)) => {
+                        log::warn!("Failed to poll sidecar process: {}", e);
+                        continue;
+                    }
+                    None => None,
+                }
+            };
+            let exit = match polled {
+                Some(exit) => exit,
+                None => {
+                    let Some(pid) = *lock(&state.adopted_pid) else {
+                        return;
+                    };
+                    let alive = tauri::async_runtime::spawn_blocking(move || pid_alive(pid)).await.unwrap_or(true);
+                    // Unless a stop or restart took it while we checked
+                    if alive || lock(&state.adopted_pid).take().is_none() {
+                        None
+                    } else {
+                        log::error!("Adopted sidecar (PID {}) is gone", pid);
+                        Some(SidecarExit::Unknown)
+                    }
+                }
+            };
+
+            let Some(exit) = exit else {
+                if watchdog.threshold == 0 || last_check.elapsed() < watchdog.interval {
+                    continue;
+                }
+                last_check = Instant::now();
+                let port = state.get_port();
+                if port == 0 {
+                    continue;
+                }
+                let url = state.endpoint().health_url(port);
+                let probe_started = Instant::now();
+                match probe_health(&client, &url, watchdog.timeout).await {
+                    Ok(()) => {
+                        if failures > 0 {
+                            log::info!("Sidecar health recovered after {} failed checks", failures);
+                        }
+                        failures = 0;
+                        let sample = latency.record(elapsed_ms(probe_started));
+                        log::trace!("Sidecar health round trip {}ms (average {}ms)", sample.last_ms, sample.average_ms);
+                        *lock(&state.health_latency_ms) = Some(sample.last_ms);
+                        *lock(&state.latency) = Some(sample.clone());
+                        if let Err(e) = app_handle.emit("sidecar-latency", sample) {
+                            log::error!("Failed to emit sidecar-latency event: {}", e);
+                        }
+                    }
+                    Err((reason, error)) => {
+                        failures += 1;
+                        log::warn!("Sidecar health check failed, {} ({}/{}): {}", reason, failures, watchdog.threshold, error);
+                        if failures >= watchdog.threshold {
+                            log::error!("Sidecar is unresponsive ({}), restarting it", reason);
+                            if let Err(e) = app_handle.emit("sidecar-unhealthy", SidecarUnhealthy { failures, reason, error }) {
+                                log::error!("Failed to emit sidecar-unhealthy event: {}", e);
+                            }
+                            if let Err(e) = restart_managed_sidecar(&app_handle, StartCause::Watchdog).await {
+                                log::warn!("Watchdog restart skipped: {}", e);
+                            }
+                            return;
+                        }
+                    }
+                }
+                continue;
+            };
+
+            let error = format!("Python sidecar {}", exit);
+            log::error!("{}", error);
+
+            let port = state.take_port();
+            *lock(&state.health) = None;
+            state.set_error(Some(error.clone()));
+            record_event(&state, SidecarEventKind::Crash, Some(port), Some(exit), Some(&error));
+
+            let payload = SidecarStartError::Exited { message: error.clone(), exit };
+            *lock(&state.start_error) = Some(payload.clone());
+            if let Err(e) = app_handle.emit("sidecar-error", payload) {
+                log::error!("Failed to emit sidecar-error event: {}", e);
+            }
+            set_sidecar_status(&app_handle, SidecarStatus::Failed { error: error.clone(), exit: Some(exit) });
+            notify_failure(&app_handle, &error);
+            shutdown::quit_on_sidecar_failure(&app_handle, &error);
+            return;
+        }
+    });
+}
+
+/// Show a native notification that the sidecar stopped, since the window may
+/// be hidden in the tray. Skipped in headless mode and within
+/// FAILURE_NOTICE_INTERVAL of the previous one.
+fn notify_failure<R: Runtime>(app: &tauri::AppHandle<R>, error: &str) {
+    if app.state::<WindowState>().headless {
+        return;
+    }
+    {
+        let state = app.state::<SidecarState>();
+        let mut last = lock(&state.last_failure_notice);
+        if last.is_some_and(|at| at.elapsed() < FAILURE_NOTICE_INTERVAL) {
+            log::debug!("Skipping failure notification, one was shown recently");
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+
+    let hotkey = app.state::<HotkeyState>().current_combo.lock().ok().and_then(|combo| combo.clone());
+    let tray = !app.state::<WindowState>().tray_disabled;
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("LightBot backend stopped")
+        .body(failure_body(error, hotkey.as_deref(), tray))
+        .show()
+    {
+        log::warn!("Failed to show failure notification: {}", e);
+    }
+}
+
+/// Failure notice text, pointing at whatever way back into the app exists
+fn failure_body(error: &str, hotkey: Option<&str>, tray: bool) -> String {
+    let open = match (tray, hotkey) {
+        (true, _) => "Open LightBot from the tray to view the logs.".to_string(),
+        (false, Some(combo)) => format!("Press {} to open LightBot and view the logs.", combo),
+        (false, None) => "Open LightBot to view the logs.".to_string(),
+    };
+    format!("{}\n{}", error, open)
+}
+
+/// Kill the spawned (or adopted) sidecar process, if we own one. Returns whether there was one.
+fn stop_child(state: &SidecarState) -> bool {
+    if let Some(pid) = state.take_adopted_pid() {
+        log::info!("Stopping adopted sidecar (PID {})", pid);
+        kill_pid(pid);
+        return true;
+    }
+    let child = state.take_child();
+    let Some(mut child) = child else {
+        return false;
+    };
+    log::info!("Stopping sidecar (PID {})", child.id());
+    terminate_child(&mut child);
+    true
+}
+
+/// Ask the sidecar to exit with SIGTERM so it can clean up, and kill it if
+/// it's still running after STOP_GRACE_PERIOD. Windows has no SIGTERM, so
+/// it's killed right away there.
+fn terminate_child(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        let pid = child.id();
+        let signaled = std::process::Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status()
+            .is_ok_and(|status| status.success());
+        if signaled {
+            let deadline = Instant::now() + STOP_GRACE_PERIOD;
+            while Instant::now() < deadline {
+                match child.try_wait() {
+                    Ok(Some(_)) => return,
+                    Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+                    Err(_) => break,
+                }
+            }
+            log::warn!("Sidecar (PID {}) still running {:?} after SIGTERM, sending SIGKILL", pid, STOP_GRACE_PERIOD);
+        }
+    }
+    if let Err(e) = child.kill() {
+        log::warn!("Failed to kill sidecar: {}", e);
+    }
+    let _ = child.wait();
+}
+
+/// Whether process `pid` still exists, for an adopted sidecar we have no
+/// `Child` handle for
+#[cfg(unix)]
+fn pid_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(windows)]
+fn pid_alive(pid: u32) -> bool {
+    use std::os::windows::process::CommandExt;
+    // Keep tasklist from flashing a console window
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .creation_flags(0x0800_0000)
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+}
+
+/// Kill a process we have no `Child` handle for
+fn kill_pid(pid: u32) {
+    #[cfg(unix)]
+    let result = std::process::Command::new("kill").arg(pid.to_string()).status();
+    #[cfg(windows)]
+    let result = {
+        use std::os::windows::process::CommandExt;
+        // Keep taskkill from flashing a console window
+        std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/F"])
+            .creation_flags(0x0800_0000)
+            .status()
+    };
+    match result {
+        Ok(status) if status.success() => {}
+        Ok(status) => log::warn!("Failed to kill sidecar PID {}: {}", pid, status),
+        Err(e) => log::warn!("Failed to kill sidecar PID {}: {}", pid, e),
+    }
+}
+
+fn detached_lock_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lightbot").join(DETACHED_LOCK_FILE))
+}
+
+/// Leave the sidecar running and record its PID and port so the next launch
+/// can re-adopt it
+fn detach_sidecar(state: &SidecarState, port: u16) {
+    let pid = match state.take_adopted_pid() {
+        Some(pid) => pid,
+        // Dropping a Child doesn't kill the process
+        None => match state.take_child() {
+            Some(child) => child.id(),
+            None => return,
+        },
+    };
+    log::warn!(
+        "KEEP_SIDECAR_ON_EXIT is set: leaving the sidecar running (PID {}, port {}). Stop it by hand or relaunch the app to re-adopt it.",
+        pid,
+        port
+    );
+    record_event(state, SidecarEventKind::Detach, Some(port), None, None);
+
+    let Some(path) = detached_lock_path() else {
+        log::warn!("No home directory for {}, the next launch won't re-adopt the sidecar", DETACHED_LOCK_FILE);
+        return;
+    };
+    let result = serde_json::to_string(&DetachedSidecar { pid, port })
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("Failed to write {:?}: {}", path, e);
+    }
+}
+
+/// Take over a sidecar a previous launch left running, if its lockfile is
+/// there and the recorded PID still answers on the recorded port. Something
+/// else answering there isn't adopted, since its PID would be the wrong one
+/// to kill; `reconnect_last_port` then connects to it as an external
+/// sidecar. The lockfile is removed either way.
+async fn adopt_detached_sidecar<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    client: &reqwest::Client,
+    endpoint: &SidecarEndpoint,
+    started: Instant,
+) -> Option<SpawnedSidecar> {
+    let path = detached_lock_path()?;
+    let contents = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    let detached: DetachedSidecar = match serde_json::from_str(&contents) {
+        Ok(detached) => detached,
+        Err(e) => {
+            log::warn!("Ignoring invalid {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    let health_url = endpoint.health_url(detached.port);
+    let request_started = Instant::now();
+    match client.get(&health_url).timeout(Duration::from_secs(2)).send().await {
+        Ok(resp) if resp.status().is_success() => {
+            let health_latency_ms = elapsed_ms(request_started);
+            let health = HealthInfo::parse(&resp.text().await.unwrap_or_default());
+            if !health.as_ref().is_some_and(|h| h.is_process(detached.pid)) {
+                log::warn!(
+                    "Port {} answers, but not from PID {} left running by the previous launch, not adopting it",
+                    detached.port,
+                    detached.pid
+                );
+                return None;
+            }
+            log::info!(
+                "Re-adopted the sidecar left running by the previous launch (PID {}, port {})",
+                detached.pid,
+                detached.port
+            );
+            let state = app.state::<SidecarState>();
+            *lock(&state.adopted_pid) = Some(detached.pid);
+            record_event(&state, SidecarEventKind::Adopt, Some(detached.port), None, None);
+            let metrics = StartupMetrics {
+                first_health_ms: Some(elapsed_ms(started)),
+                health_latency_ms: Some(health_latency_ms),
+                total_ms: elapsed_ms(started),
+                attempts: 1,
+                ..Default::default()
+            };
+            Some(SpawnedSidecar { child: None, port: detached.port, health, metrics })
+        }
+        _ => {
+            log::info!(
+                "Sidecar left running by the previous launch (PID {}, port {}) isn't answering, starting a new one",
+                detached.pid,
+                detached.port
+            );
+            None
+        }
+    }
+}
+
+fn last_port_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lightbot").join(LAST_PORT_FILE))
+}
+
+/// Remember a sidecar that just became healthy for `reconnect_last_port`,
+/// with its PID when it's ours (`owned_pid`)
+fn save_last_port(port: u16, owned_pid: Option<u32>) {
+    let Some(path) = last_port_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let last = LastSidecar { port, pid: owned_pid, owned: owned_pid.is_some() };
+    let result = serde_json::to_string(&last)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("Failed to write {:?}: {}", path, e);
+    }
+}
+
+/// Parse the last-sidecar file; older launches wrote just the port
+fn parse_last_sidecar(contents: &str) -> Option<LastSidecar> {
+    let contents = contents.trim();
+    let last = match serde_json::from_str::<LastSidecar>(contents) {
+        Ok(last) => last,
+        Err(_) => LastSidecar { port: contents.parse().ok()?, pid: None, owned: false },
+    };
+    (last.port > 0).then_some(last)
+}
+
+/// Reuse a sidecar still answering on the last healthy port instead of
+/// spawning another. Only a healthy /health body that identifies itself as a
+/// LightBot sidecar counts, so an unrelated server on that port is ignored.
+/// A sidecar the app owned (orphaned when a previous launch crashed) is
+/// adopted if its PID still matches; any other one, e.g. started by hand,
+/// isn't ours to stop, like a manual one.
+async fn reconnect_last_port<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    client: &reqwest::Client,
+    endpoint: &SidecarEndpoint,
+    started: Instant,
+) -> Option<SpawnedSidecar> {
+    let path = last_port_path()?;
+    let last = parse_last_sidecar(&std::fs::read_to_string(&path).ok()?)?;
+    let port = last.port;
+
+    let request_started = Instant::now();
+    let resp = match client.get(endpoint.health_url(port)).timeout(LAST_PORT_PROBE_TIMEOUT).send().await {
+        Ok(resp) if resp.status().is_success() => resp,
+        _ => {
+            log::debug!("Nothing answering on the last sidecar port {}", port);
+            return None;
+        }
+    };
+    let health_latency_ms = elapsed_ms(request_started);
+    let health = HealthInfo::parse(&resp.text().await.unwrap_or_default());
+    let Some(info) = health.as_ref().filter(|h| h.is_ready_sidecar()) else {
+        log::info!("Port {} answered /health but not like a ready LightBot sidecar, spawning a new one", port);
+        return None;
+    };
+
+    let state = app.state::<SidecarState>();
+    match last.pid.filter(|pid| last.owned && info.is_process(*pid)) {
+        Some(pid) => {
+            log::info!("Re-adopted the sidecar orphaned by the previous launch (PID {}, port {})", pid, port);
+            *lock(&state.adopted_pid) = Some(pid);
+            record_event(&state, SidecarEventKind::Adopt, Some(port), None, None);
+        }
+        None => {
+            log::info!("Reconnected to the sidecar still running on the last port {}", port);
+            record_event(&state, SidecarEventKind::Reconnect, Some(port), None, None);
+        }
+    }
+    let metrics = StartupMetrics {
+        first_health_ms: Some(elapsed_ms(started)),
+        health_latency_ms: Some(health_latency_ms),
+        total_ms: elapsed_ms(started),
+        attempts: 1,
+        ..Default::default()
+    };
+    Some(SpawnedSidecar { child: None, port, health, metrics })
+}
+
+/// Kill the spawned sidecar before the app exits, or leave it running for the
+/// next launch with KEEP_SIDECAR_ON_EXIT. A manual sidecar is left running.
+pub fn stop_sidecar<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<SidecarState>();
+    let port = state.take_port();
+    if state.keep_on_exit() {
+        detach_sidecar(&state, port);
+        return;
+    }
+    if stop_child(&state) {
+        record_event(&state, SidecarEventKind::Stop, Some(port), None, None);
+    }
+}
+
+/// KEEP_SIDECAR_ON_EXIT, for backend development against a sidecar that
+/// outlives the app
+pub fn keep_on_exit_from_env() -> bool {
+    std::env::var("KEEP_SIDECAR_ON_EXIT")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Stop the current sidecar and start a fresh one. A running external
+/// sidecar is refused, since it isn't ours to kill; one that failed its
+/// health check can be re-verified.
+pub async fn restart_managed_sidecar<R: Runtime>(app: &tauri::AppHandle<R>, cause: StartCause) -> Result<(), String> {
+    claim_restart(&app.state::<SidecarState>())?;
+
+    log::info!("Restarting Python sidecar");
+    // Published before the stop, so nothing sees Ready on a port that's going away
+    set_sidecar_status(app, SidecarStatus::Starting);
+    // The SIGTERM grace period blocks, keep it off the async workers
+    let handle = app.clone();
+    if let Err(e) = tauri::async_runtime::spawn_blocking(move || reset_for_restart(&handle.state::<SidecarState>())).await {
+        log::error!("Failed to stop sidecar before restart: {}", e);
+    }
+
+    run_start(app, cause).await;
+    Ok(())
+}
+
+/// Claim `starting` for a restart in one step, so a concurrent start or
+/// restart is refused instead of racing this one. Released again when the
+/// restart is refused; otherwise the start that follows releases it.
+fn claim_restart(state: &SidecarState) -> Result<(), String> {
+    if state.starting.swap(true, Ordering::SeqCst) {
+        return Err("Sidecar is already starting".to_string());
+    }
+    let port = state.get_port();
+    if port != 0 && !state.is_managed() {
+        state.starting.store(false, Ordering::SeqCst);
+        return Err(format!(
+            "The sidecar on port {} is external (not started by LightBot), restart it yourself",
+            port
+        ));
+    }
+    Ok(())
+}
+
+/// Stop the current sidecar and clear what its run left in state, ready for
+/// a fresh start. Blocks for up to STOP_GRACE_PERIOD.
+fn reset_for_restart(state: &SidecarState) {
+    state.restarts.fetch_add(1, Ordering::SeqCst);
+    stop_child(state);
+    let port = state.take_port();
+    record_event(state, SidecarEventKind::Restart, (port != 0).then_some(port), None, None);
+    state.set_error(None);
+    *lock(&state.health) = None;
+    *lock(&state.latency) = None;
+    *lock(&state.progress) = None;
+    *lock(&state.start_error) = None;
+}
+
+pub fn status_report(state: &SidecarState) -> SidecarStatusReport {
+    SidecarStatusReport {
+        port: state.get_port(),
+        error: state.get_error(),
+        starting: state.is_starting(),
+        managed: state.is_managed(),
+    }
+}
+
+/// Catch-up for a frontend that mounted after the lifecycle events were sent
+#[tauri::command]
+pub fn get_full_status(state: tauri::State<SidecarState>) -> SidecarFullStatus {
+    state.full_status()
+}
+
+#[tauri::command]
+pub fn is_sidecar_managed(state: tauri::State<SidecarState>) -> bool {
+    state.is_managed()
+}
+
+#[tauri::command]
+pub fn get_sidecar_status(state: tauri::State<SidecarState>) -> SidecarStatusReport {
+    status_report(&state)
+}
+
+#[tauri::command]
+pub async fn restart_sidecar(app: tauri::AppHandle) -> Result<(), String> {
+    restart_managed_sidecar(&app, StartCause::User).await
+}
+
+/// Result of `set_sidecar_port`
+#[derive(Clone, Serialize)]
+pub struct PortChange {
+    pub old_port: u16,
+    pub new_port: u16,
+}
+
+/// Move the managed sidecar to `port`: check the port is free, stop the
+/// sidecar gracefully and spawn a fresh one bound to exactly that port. The
+/// usual restart events are emitted along the way. Refused for a manual or
+/// reconnected sidecar, which the app doesn't own. If the new port doesn't
+/// work out, the sidecar is put back on its old one.
+#[tauri::command]
+pub async fn set_sidecar_port(app: tauri::AppHandle, port: u16) -> Result<PortChange, String> {
+    if port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    if let Some(manual) = manual_port() {
+        return Err(format!(
+            "Not changing the port of the external sidecar on port {} (--sidecar-port / LIGHTBOT_SIDECAR_PORT)",
+            manual
+        ));
+    }
+
+    let state = app.state::<SidecarState>();
+    let old_port = state.get_port();
+    if old_port != 0 && !state.is_managed() {
+        return Err(format!(
+            "The sidecar on port {} is external (not started by LightBot), not changing its port",
+            old_port
+        ));
+    }
+    if old_port == port {
+        return Ok(PortChange { old_port, new_port: port });
+    }
+    if !portpicker::is_free(port) {
+        return Err(format!("Port {} is already in use", port));
+    }
+
+    log::info!("Moving Python sidecar from port {} to {}", old_port, port);
+    restart_on_port(&app, port).await?;
+    if state.get_port() == port {
+        return Ok(PortChange { old_port, new_port: port });
+    }
+
+    let reason = state.get_error().unwrap_or_else(|| "sidecar did not start".to_string());
+    log::warn!("Sidecar failed on port {}: {}", port, reason);
+    if old_port != 0 {
+        log::info!("Putting the sidecar back on port {}", old_port);
+        if let Err(e) = restart_on_port(&app, old_port).await {
+            log::error!("Failed to restore the sidecar on port {}: {}", old_port, e);
+        } else if state.get_port() != old_port {
+            log::error!("Failed to restore the sidecar on port {}", old_port);
+        }
+    }
+    Err(format!("Failed to move sidecar to port {}: {}", port, reason))
+}
+
+/// Restart the managed sidecar bound to exactly `port`
+async fn restart_on_port<R: Runtime>(app: &tauri::AppHandle<R>, port: u16) -> Result<(), String> {
+    let state = app.state::<SidecarState>();
+    *lock(&state.requested_port) = Some(port);
+    let restarted = restart_managed_sidecar(app, StartCause::User).await;
+    // Only consumed if the restart got as far as spawning
+    *lock(&state.requested_port) = None;
+    restarted
+}
+
+/// Port of the running sidecar, or why there isn't one
+fn current_port(state: &SidecarState) -> Result<u16, SidecarError> {
+    let port = state.get_port();
+    if port == 0 {
+        let error = state.get_error();
+        return Err(SidecarError::NotReady(error.unwrap_or_else(|| "Sidecar not started yet".to_string())));
+    }
+    Ok(port)
+}
+
+/// Port of the sidecar once it has finished starting. Waits up to `wait`
+/// for a start in flight; a failed or stopped sidecar errors right away.
+async fn wait_for_port(state: &SidecarState, wait: Duration) -> Result<u16, SidecarError> {
+    let mut status = state.status.subscribe();
+    let settled = tokio::time::timeout(wait, status.wait_for(|s| !matches!(s, SidecarStatus::Starting)))
+        .await
+        .is_ok();
+    if !settled {
+        return Err(SidecarError::HealthTimeout(format!("not ready after {}ms", wait.as_millis())));
+    }
+    current_port(state)
+}
+
+/// Forward an HTTP request to the sidecar on its current port and return
+/// the status and body. A request made while the sidecar is starting waits
+/// for it, up to `wait_ms` (default 30s; 0 doesn't wait). The request itself
+/// may take `timeout_ms`, else SIDECAR_REQUEST_TIMEOUT_MS, else 30s.
+/// Responses are buffered, so streaming endpoints (e.g. /chat/stream) should
+/// still be fetched directly.
+#[tauri::command]
+pub async fn sidecar_request(
+    state: tauri::State<'_, SidecarState>,
+    method: String,
+    path: String,
+    body: Option<String>,
+    wait_ms: Option<u64>,
+    timeout_ms: Option<u64>,
+) -> Result<SidecarResponse, SidecarError> {
+    let wait = wait_ms.map(Duration::from_millis).unwrap_or(DEFAULT_PROXY_READY_WAIT);
+    let port = wait_for_port(&state, wait).await?;
+
+    let method = reqwest::Method::from_bytes(method.trim().to_ascii_uppercase().as_bytes())
+        .map_err(|_| SidecarError::InvalidRequest(format!("Unknown HTTP method '{}'", method)))?;
+    if !path.starts_with('/') {
+        return Err(SidecarError::InvalidRequest(format!("Path must start with '/': {}", path)));
+    }
+
+    let url = format!("{}{}", state.endpoint().base_url(port), path);
+    log::debug!("Proxying {} {}", method, path);
+
+    let timeout = timeout_ms
+        .filter(|ms| *ms > 0)
+        .unwrap_or_else(|| env_number("SIDECAR_REQUEST_TIMEOUT_MS", DEFAULT_REQUEST_TIMEOUT_MS));
+    let mut request = state.client().request(method, &url).timeout(Duration::from_millis(timeout));
+    if let Some(body) = body {
+        request = request.header(reqwest::header::CONTENT_TYPE, "application/json").body(body);
+    }
+
+    let response = request.send().await.map_err(|e| {
+        if e.is_timeout() {
+            SidecarError::RequestTimeout(format!("{} after {}ms", path, timeout))
+        } else {
+            SidecarError::Request(e.to_string())
+        }
+    })?;
+    let status = response.status().as_u16();
+    let body = response.text().await.map_err(|e| SidecarError::Request(e.to_string()))?;
+
+    Ok(SidecarResponse { status, body })
+}
+
+/// Payload of the `sidecar-reloaded` event
+#[derive(Clone, Serialize)]
+struct SidecarReloaded {
+    /// Whether the sidecar had no reload route and was restarted instead
+    restarted: bool,
+}
+
+/// Ask the sidecar to re-read its config without restarting it. A sidecar
+/// without the reload route (404, 405 or 501) gets a full restart instead.
+/// Emits `sidecar-reloaded` once it's done.
+#[tauri::command]
+pub async fn reload_sidecar_config(app: tauri::AppHandle) -> Result<(), SidecarError> {
+    let state = app.state::<SidecarState>();
+    let port = current_port(&state)?;
+    let url = state.endpoint().reload_url(port);
+
+    let response = state
+        .client()
+        .post(&url)
+        .timeout(RELOAD_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                SidecarError::RequestTimeout(url.clone())
+            } else {
+                SidecarError::Request(e.to_string())
+            }
+        })?;
+    let status = response.status();
+
+    let restarted = if status.is_success() {
+        log::info!("Sidecar reloaded its config");
+        false
+    } else if matches!(status.as_u16(), 404 | 405 | 501) {
+        log::info!("Sidecar can't reload its config ({}), restarting it instead", status);
+        restart_managed_sidecar(&app, StartCause::User).await.map_err(SidecarError::NotReady)?;
+        current_port(&state)?;
+        true
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        return Err(SidecarError::Request(format!("Reload returned {}: {}", status, body.trim())));
+    };
+
+    if let Err(e) = app.emit("sidecar-reloaded", SidecarReloaded { restarted }) {
+        log::error!("Failed to emit sidecar-reloaded event: {}", e);
+    }
+    Ok(())
+}
+
+/// Hit the health route right now on the stored port and report latency and the body.
+/// Unlike `get_sidecar_status`, this verifies the server is still responsive.
+#[tauri::command]
+pub async fn check_sidecar_health(
+    state: tauri::State<'_, SidecarState>,
+) -> Result<HealthReport, SidecarError> {
+    let port = current_port(&state)?;
+    let health_url = state.endpoint().health_url(port);
+
+    let started = Instant::now();
+    let response = state
+        .client()
+        .get(&health_url)
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                SidecarError::RequestTimeout(health_url.clone())
+            } else {
+                SidecarError::Request(e.to_string())
+            }
+        })?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| SidecarError::Request(e.to_string()))?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    if status.is_success() {
+        *lock(&state.health_latency_ms) = Some(latency_ms);
+    }
+
+    log::debug!("Health check on port {}: {} in {}ms", port, status, latency_ms);
+    Ok(HealthReport {
+        port,
+        healthy: status.is_success(),
+        status: status.as_u16(),
+        latency_ms,
+        health: HealthInfo::parse(&text),
+    })
+}
+
+/// Recent lifecycle events, oldest first
+#[tauri::command]
+pub fn get_sidecar_history(state: tauri::State<SidecarState>) -> Vec<SidecarEvent> {
+    state.history()
+}
+
+#[tauri::command]
+pub fn get_startup_metrics(state: tauri::State<SidecarState>) -> Option<StartupMetrics> {
+    state.startup_metrics()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    const TEST_POLL: ReadinessPoll = ReadinessPoll {
+        initial_delay: Duration::ZERO,
+        fast_interval: Duration::from_millis(100),
+        fast_window: Duration::ZERO,
+        interval: Duration::from_millis(100),
+        retries: 50,
+        request_timeout: Duration::from_secs(1),
+    };
+
+    /// Spawn tests/mock_sidecar.sh in `mode` and wait for it like startup does,
+    /// returning the outcome, the progress seen on each poll and the port
+    #[cfg(unix)]
+    async fn start_mock(mode: &str, poll: &ReadinessPoll) -> (Result<ReadySidecar, SpawnError>, Vec<Option<f64>>, u16) {
+        let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("mock_sidecar.sh");
+        let port = portpicker::pick_unused_port().expect("no free port");
+        let args = vec!["--mode".to_string(), mode.to_string()];
+        let child = spawn_child(&[], &script, None, port, &args, &[], false).unwrap_or_else(|_| panic!("failed to spawn {:?}", script));
+
+        let progress = Mutex::new(Vec::new());
+        let url = SidecarEndpoint::default().ready_url(port);
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let result = wait_until_ready(&reqwest::Client::new(), &url, child, &AtomicBool::new(false), deadline, poll, |health| {
+            progress.lock().unwrap().push(health.and_then(|h| h.progress))
+        })
+        .await;
+        (result, progress.into_inner().unwrap(), port)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn mock_sidecar_becomes_ready() {
+        let (result, progress, _) = start_mock("ready", &TEST_POLL).await;
+        let Ok(ready) = result else {
+            panic!("mock sidecar never became ready");
+        };
+        assert_eq!(ready.health.as_ref().map(|h| h.status.as_str()), Some("healthy"));
+        assert_eq!(progress.last(), Some(&Some(100.0)));
+        kill_child(ready.child);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn crash_is_fatal_and_a_restart_can_still_succeed() {
+        let state = test_state();
+        let (result, _, _) = start_mock("crash", &TEST_POLL).await;
+        let Err(SpawnError::Fatal(error)) = result else {
+            panic!("a crashing sidecar should fail fatally");
+        };
+        assert!(error.contains("exited with code 3"), "{}", error);
+        // Publish the failure the way start_sidecar does
+        state.set_error(Some(error.clone()));
+        state.set_status(SidecarStatus::Failed { error, exit: None });
+
+        claim_restart(&state).expect("a failed sidecar can be restarted");
+        reset_for_restart(&state);
+        assert_eq!(state.get_error(), None);
+        assert_eq!(state.restarts(), 1);
+
+        let (result, _, port) = start_mock("ready", &TEST_POLL).await;
+        let Ok(ready) = result else {
+            panic!("restarted sidecar never became ready");
+        };
+        // Publish the start the way run_start does, releasing `starting`
+        *lock(&state.child) = Some(ready.child);
+        state.set_port(port);
+        state.managed.store(true, Ordering::SeqCst);
+        state.starting.store(false, Ordering::SeqCst);
+        assert_eq!(current_port(&state).ok(), Some(port));
+
+        // Restarting the running sidecar stops it and clears the port
+        claim_restart(&state).expect("a managed sidecar can be restarted");
+        reset_for_restart(&state);
+        assert!(lock(&state.child).is_none());
+        assert_eq!(state.get_port(), 0);
+        assert_eq!(state.restarts(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pid_liveness_follows_the_process() {
+        assert!(pid_alive(std::process::id()));
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!pid_alive(pid));
+    }
+
+    #[test]
+    fn last_sidecar_file_reads_both_formats() {
+        let last = parse_last_sidecar(r#"{"port": 8765, "pid": 4242, "owned": true}"#).unwrap();
+        assert_eq!((last.port, last.pid, last.owned), (8765, Some(4242), true));
+        let last = parse_last_sidecar("8765\n").unwrap();
+        assert_eq!((last.port, last.pid, last.owned), (8765, None, false));
+        assert!(parse_last_sidecar("0").is_none());
+        assert!(parse_last_sidecar("garbage").is_none());
+    }
+
+    #[test]
+    fn ready_sidecar_must_identify_itself() {
+        let ours = HealthInfo::parse(r#"{"status": "healthy", "app": "lightbot-sidecar", "pid": 10, "ppid": 9}"#).unwrap();
+        assert!(ours.is_ready_sidecar());
+        assert!(ours.is_process(10) && ours.is_process(9) && !ours.is_process(11));
+        let other = HealthInfo::parse(r#"{"status": "healthy"}"#).unwrap();
+        assert!(!other.is_ready_sidecar());
+    }
+
+    #[test]
+    fn missing_executable_fails_fatally() {
+        let missing = std::env::temp_dir().join(format!("lightbot-no-sidecar-{}", std::process::id()));
+        assert_eq!(find_sidecar(std::slice::from_ref(&missing)), None);
+        let result = spawn_child(&[], &missing, None, 8765, &[], &[], false);
+        assert!(matches!(result, Err(SpawnError::Fatal(error)) if error.starts_with("Failed to spawn sidecar")));
+    }
+
+    #[test]
+    fn restart_is_refused_while_starting_or_external() {
+        let state = test_state();
+        assert!(claim_restart(&state).is_ok());
+        assert!(state.is_starting());
+        // A second restart racing the first is refused
+        assert!(claim_restart(&state).is_err());
+
+        state.starting.store(false, Ordering::SeqCst);
+        state.set_port(4242);
+        assert!(claim_restart(&state).is_err());
+        assert!(!state.is_starting(), "a refused restart must release the claim");
+        state.managed.store(true, Ordering::SeqCst);
+        assert!(claim_restart(&state).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unhealthy_sidecar_is_retried_then_given_up() {
+        let poll = ReadinessPoll { retries: 3, ..TEST_POLL };
+        let (result, progress, _) = start_mock("unhealthy", &poll).await;
+        assert!(matches!(result, Err(SpawnError::Retryable(_))));
+        assert!(progress.contains(&Some(40.0)));
+    }
+
+    fn test_state() -> SidecarState {
+        SidecarState::new(reqwest::Client::new(), false)
+    }
+
+    #[tokio::test]
+    async fn request_waits_for_a_starting_sidecar() {
+        let state = test_state();
+        let timed_out = wait_for_port(&state, Duration::from_millis(50)).await;
+        assert!(matches!(timed_out, Err(SidecarError::HealthTimeout(_))));
+
+        let (port, _) = tokio::join!(wait_for_port(&state, Duration::from_secs(5)), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            state.set_port(4242);
+            state.set_status(SidecarStatus::Ready { port: 4242 });
+        });
+        assert_eq!(port.ok(), Some(4242));
+    }
+
+    #[tokio::test]
+    async fn request_to_a_failed_sidecar_does_not_wait() {
+        let state = test_state();
+        state.set_error(Some("boom".to_string()));
+        state.set_status(SidecarStatus::Failed { error: "boom".to_string(), exit: None });
+        let result = wait_for_port(&state, Duration::from_secs(60)).await;
+        assert!(matches!(result, Err(SidecarError::NotReady(msg)) if msg == "boom"));
+    }
+
+    #[test]
+    fn failed_port_change_does_not_quit() {
+        // set_sidecar_port and the other commands restart as the user, so a
+        // bad port can't end the app even with QUIT_ON_SIDECAR_FAILURE set
+        assert!(!StartCause::User.may_quit());
+        assert!(StartCause::Launch.may_quit());
+        assert!(StartCause::Watchdog.may_quit());
+    }
+
+    #[test]
+    fn status_survives_a_poisoned_lock() {
+        let state = test_state();
+        state.set_port(4242);
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = state.port.write().unwrap();
+            panic!("poison the port lock");
+        }));
+        assert!(state.port.is_poisoned());
+        assert_eq!(status_report(&state).port, 4242);
+        assert_eq!(current_port(&state).ok(), Some(4242));
+    }
+
+    #[test]
+    fn sidecar_host_may_carry_a_scheme() {
+        assert_eq!(split_scheme("127.0.0.1"), Ok(("http".to_string(), "127.0.0.1".to_string())));
+        assert_eq!(split_scheme("HTTPS://sidecar.lan/"), Ok(("https".to_string(), "sidecar.lan".to_string())));
+        assert!(split_scheme("ftp://sidecar.lan").is_err());
+
+        let endpoint = SidecarEndpoint { scheme: "https".to_string(), host: "::1".to_string(), ..Default::default() };
+        assert_eq!(endpoint.health_url(8443), "https://[::1]:8443/health");
+    }
+
+    #[test]
+    fn manual_port_is_trimmed_and_unquoted() {
+        assert_eq!(parse_manual_port(""), Ok(None));
+        assert_eq!(parse_manual_port("   "), Ok(None));
+        assert_eq!(parse_manual_port("''"), Ok(None));
+        assert_eq!(parse_manual_port(" 8080 "), Ok(Some(8080)));
+        assert_eq!(parse_manual_port("'8080'"), Ok(Some(8080)));
+        assert_eq!(parse_manual_port("\"8080\""), Ok(Some(8080)));
+    }
+
+    #[test]
+    fn manual_port_rejects_garbage() {
+        assert!(parse_manual_port("abc").is_err());
+        assert!(parse_manual_port("8080x").is_err());
+        assert!(parse_manual_port("0").is_err());
+        assert!(parse_manual_port("70000").is_err());
+        assert!(parse_manual_port("-1").is_err());
+    }
+
+    #[test]
+    fn log_level_reply_can_report_an_error() {
+        assert_eq!(log_level_rejection(r#"{"status": "updated", "level": "debug"}"#), None);
+        assert_eq!(log_level_rejection(""), None);
+        assert_eq!(
+            log_level_rejection(r#"{"status": "error", "message": "Unknown log level 'verbose'"}"#),
+            Some("Unknown log level 'verbose'".to_string())
+        );
+        assert_eq!(log_level_rejection(r#"{"status": "error"}"#), Some("no reason given".to_string()));
+    }
+
+    #[test]
+    fn failure_notice_points_at_the_tray_or_the_hotkey() {
+        assert_eq!(failure_body("boom", Some("Ctrl+Shift+O"), true), "boom\nOpen LightBot from the tray to view the logs.");
+        assert_eq!(failure_body("boom", Some("Ctrl+Shift+O"), false), "boom\nPress Ctrl+Shift+O to open LightBot and view the logs.");
+        assert_eq!(failure_body("boom", None, false), "boom\nOpen LightBot to view the logs.");
+    }
+
+    #[test]
+    fn latency_average_covers_the_recent_window() {
+        let mut window = LatencyWindow::default();
+        assert_eq!(window.record(10), SidecarLatency { last_ms: 10, average_ms: 10, samples: 1 });
+        assert_eq!(window.record(30).average_ms, 20);
+        for _ in 0..LATENCY_WINDOW {
+            window.record(5);
+        }
+        assert_eq!(window.record(5), SidecarLatency { last_ms: 5, average_ms: 5, samples: LATENCY_WINDOW });
+    }
+
+    #[test]
+    fn search_paths_keep_order_and_drop_duplicates() {
+        let candidates = vec![
+            Some(PathBuf::from("/app/python-sidecar-x")),
+            None,
+            Some(PathBuf::from("/app/python-sidecar")),
+            Some(PathBuf::from("/app/python-sidecar-x")),
+            Some(PathBuf::from("bin/python-sidecar")),
+        ];
+        assert_eq!(
+            order_search_paths(None, candidates),
+            vec![
+                PathBuf::from("/app/python-sidecar-x"),
+                PathBuf::from("/app/python-sidecar"),
+                PathBuf::from("bin/python-sidecar"),
+            ]
+        );
+    }
+
+    #[test]
+    fn search_path_override_is_used_exclusively() {
+        let candidates = vec![Some(PathBuf::from("/app/python-sidecar"))];
+        assert_eq!(
+            order_search_paths(Some(PathBuf::from("/opt/sidecar")), candidates),
+            vec![PathBuf::from("/opt/sidecar")]
+        );
+    }
+
+    #[test]
+    fn find_sidecar_returns_first_existing_candidate() {
+        let dir = std::env::temp_dir().join(format!("lightbot-find-sidecar-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("python-sidecar");
+        std::fs::write(&binary, "").unwrap();
+
+        let missing = dir.join("missing");
+        let candidates = vec![missing.clone(), binary.clone(), dir.clone()];
+        assert_eq!(find_sidecar(&candidates), Some((1, binary)));
+        assert_eq!(find_sidecar(&[missing]), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,497 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::ImageError;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{Manager, Runtime, Theme};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::shutdown;
+use crate::sidecar::{SidecarState, SidecarStatus};
+use crate::window::{
+    apply_always_on_top, current_theme, show_main_window, toggle_window_visibility, VisibilitySource, WindowState,
+};
+
+const TRAY_ID: &str = "main-tray";
+
+/// Long errors (e.g. the list of checked sidecar paths) are cut to keep the tooltip readable
+const TOOLTIP_MAX_ERROR_CHARS: usize = 100;
+
+/// Extensions tried, in order, for icon overrides in ~/.lightbot/icons
+const ICON_OVERRIDE_EXTENSIONS: [&str; 3] = ["png", "ico", "svg"];
+
+/// Edge length SVG icons are rasterized at
+const SVG_ICON_SIZE: u32 = 64;
+
+/// Logical edge length of a tray icon slot; scaled by the display to pick a bundled size
+const TRAY_ICON_LOGICAL_SIZE: f64 = 16.0;
+
+/// Size used when the display's scale factor is unknown
+const FALLBACK_TRAY_ICON_SIZE: u32 = 32;
+
+/// One tray icon at each bundled size. `name` is also the override file stem.
+struct TrayIconSet {
+    name: &'static str,
+    x16: &'static [u8],
+    x32: &'static [u8],
+    x64: &'static [u8],
+}
+
+impl TrayIconSet {
+    fn bytes(&self, size: u32) -> &'static [u8] {
+        match size {
+            16 => self.x16,
+            64 => self.x64,
+            _ => self.x32,
+        }
+    }
+}
+
+macro_rules! tray_icon_set {
+    ($name:literal) => {
+        TrayIconSet {
+            name: $name,
+            x16: include_bytes!(concat!("../icons/", $name, "_16.png")),
+            x32: include_bytes!(concat!("../icons/", $name, "_32.png")),
+            x64: include_bytes!(concat!("../icons/", $name, ".png")),
+        }
+    };
+}
+
+const TRAY_ICON: TrayIconSet = tray_icon_set!("tray_light");
+const TRAY_ICON_STARTING: TrayIconSet = tray_icon_set!("tray_starting");
+const TRAY_ICON_ERROR: TrayIconSet = tray_icon_set!("tray_error");
+const TRAY_ICON_DARK: TrayIconSet = tray_icon_set!("tray_dark");
+const TRAY_ICON_STARTING_DARK: TrayIconSet = tray_icon_set!("tray_starting_dark");
+const TRAY_ICON_ERROR_DARK: TrayIconSet = tray_icon_set!("tray_error_dark");
+
+/// What a left click on the tray icon does, from TRAY_CLICK_ACTION.
+/// Right click always opens the menu.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum TrayClickAction {
+    /// Show the window, or hide it if it's already focused
+    Toggle,
+    /// Open the tray menu (platform default)
+    Menu,
+    /// Show and focus the window, never hide it
+    Show,
+}
+
+impl TrayClickAction {
+    fn from_env() -> Self {
+        match std::env::var("TRAY_CLICK_ACTION") {
+            Ok(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+                "toggle" => Self::Toggle,
+                "show" => Self::Show,
+                "menu" | "" => Self::Menu,
+                other => {
+                    log::warn!("Unknown TRAY_CLICK_ACTION '{}', expected toggle|menu|show. Using 'menu'.", other);
+                    Self::Menu
+                }
+            },
+            Err(_) => Self::Menu,
+        }
+    }
+}
+
+/// Handles to tray menu items whose content changes after the menu is built,
+/// plus what the icon currently reflects so it can be redrawn on theme or
+/// display changes
+struct TrayMenuState<R: Runtime> {
+    status_item: MenuItem<R>,
+    copy_url_item: MenuItem<R>,
+    always_on_top_item: CheckMenuItem<R>,
+    status: Mutex<SidecarStatus>,
+    theme: Mutex<Theme>,
+    /// Edge length of the bundled icons in use, from `tray_icon_for_scale`
+    icon_size: Mutex<u32>,
+}
+
+/// Decode icon bytes to RGBA image data. The format is detected from the
+/// bytes: PNG and ICO via the image crate, SVG rasterized with resvg.
+fn load_png_icon(bytes: &[u8]) -> Result<tauri::image::Image<'static>, ImageError> {
+    if looks_like_svg(bytes) {
+        return rasterize_svg(bytes);
+    }
+    let img = image::load_from_memory(bytes)?;
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    Ok(tauri::image::Image::new_owned(rgba.into_raw(), width, height))
+}
+
+fn looks_like_svg(bytes: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(256)]);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg"))
+}
+
+fn rasterize_svg(bytes: &[u8]) -> Result<tauri::image::Image<'static>, ImageError> {
+    let svg_error = |e: String| {
+        ImageError::Decoding(DecodingError::new(ImageFormatHint::Name("SVG".to_string()), e))
+    };
+
+    let tree = resvg::usvg::Tree::from_data(bytes, &resvg::usvg::Options::default())
+        .map_err(|e| svg_error(e.to_string()))?;
+    let size = tree.size();
+    let scale = SVG_ICON_SIZE as f32 / size.width().max(size.height());
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(SVG_ICON_SIZE, SVG_ICON_SIZE)
+        .ok_or_else(|| svg_error("Failed to allocate pixmap".to_string()))?;
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // tiny-skia stores premultiplied alpha; tray icons expect straight RGBA
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Ok(tauri::image::Image::new_owned(rgba, SVG_ICON_SIZE, SVG_ICON_SIZE))
+}
+
+/// Embedded icon that failed to decode: log it and use the default 32x32
+/// tray icon, or a transparent pixel if even that is unusable
+fn fallback_icon(name: &str, error: ImageError) -> tauri::image::Image<'static> {
+    log::error!("Failed to decode tray icon {}: {}", name, error);
+    load_png_icon(TRAY_ICON.x32).unwrap_or_else(|_| tauri::image::Image::new_owned(vec![0; 4], 1, 1))
+}
+
+/// Directory checked for user-supplied tray icons (~/.lightbot/icons)
+fn icon_override_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lightbot").join("icons"))
+}
+
+/// Load `name` (a file stem) from the icon override directory, trying each
+/// supported extension, falling back to the embedded copy
+fn resolve_icon(name: &str, embedded: &[u8]) -> tauri::image::Image<'static> {
+    if let Some(dir) = icon_override_dir() {
+        for extension in ICON_OVERRIDE_EXTENSIONS {
+            let path = dir.join(format!("{}.{}", name, extension));
+            match std::fs::read(&path) {
+                Ok(bytes) => match load_png_icon(&bytes) {
+                    Ok(icon) => return icon,
+                    Err(e) => log::warn!("Ignoring tray icon {:?}: {}", path, e),
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("Failed to read tray icon {:?}: {}", path, e),
+            }
+        }
+    }
+    load_png_icon(embedded).unwrap_or_else(|e| fallback_icon(name, e))
+}
+
+/// Bundled icon size (16, 32 or 64) for a display scale factor: the
+/// smallest that covers the tray slot without upscaling
+fn tray_icon_for_scale(scale: f64) -> u32 {
+    if !scale.is_finite() || scale <= 0.0 {
+        return FALLBACK_TRAY_ICON_SIZE;
+    }
+    let needed = TRAY_ICON_LOGICAL_SIZE * scale;
+    [16, 32].into_iter().find(|&size| needed <= size as f64).unwrap_or(64)
+}
+
+/// Scale factor of the display the tray sits on. Trays live on the primary
+/// display; the main window's display is the next best guess.
+fn tray_scale<R: Runtime>(app: &tauri::AppHandle<R>) -> Option<f64> {
+    match app.primary_monitor() {
+        Ok(Some(monitor)) => Some(monitor.scale_factor()),
+        _ => app.get_webview_window("main").and_then(|window| window.scale_factor().ok()),
+    }
+}
+
+fn tray_icon_size<R: Runtime>(app: &tauri::AppHandle<R>) -> u32 {
+    tray_scale(app).map(tray_icon_for_scale).unwrap_or(FALLBACK_TRAY_ICON_SIZE)
+}
+
+/// Icon for a sidecar status in the given system appearance, at `size` pixels
+fn tray_icon_for(status: &SidecarStatus, theme: Theme, size: u32) -> tauri::image::Image<'static> {
+    let dark = theme == Theme::Dark;
+    let set = match (status, dark) {
+        (SidecarStatus::Starting, false) => &TRAY_ICON_STARTING,
+        (SidecarStatus::Starting, true) => &TRAY_ICON_STARTING_DARK,
+        (SidecarStatus::Ready { .. }, false) => &TRAY_ICON,
+        (SidecarStatus::Ready { .. }, true) => &TRAY_ICON_DARK,
+        (SidecarStatus::Failed { .. }, false) => &TRAY_ICON_ERROR,
+        (SidecarStatus::Failed { .. }, true) => &TRAY_ICON_ERROR_DARK,
+    };
+    resolve_icon(set.name, set.bytes(size))
+}
+
+/// Whether to skip the tray icon: DISABLE_TRAY if set, otherwise the config file
+pub fn disabled_from_env(config: Option<bool>) -> bool {
+    match std::env::var("DISABLE_TRAY") {
+        Ok(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            "0" | "false" | "no" | "off" => false,
+            other => {
+                log::warn!("Ignoring invalid DISABLE_TRAY '{}', expected true or false", other);
+                config.unwrap_or(false)
+            }
+        },
+        Err(_) => config.unwrap_or(false),
+    }
+}
+
+pub fn setup_system_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
+    // Get current setting from state
+    let window_state = app.state::<WindowState>();
+    let visible_on_all = *window_state.visible_on_all_workspaces.lock().unwrap();
+    let always_on_top = *window_state.always_on_top.lock().unwrap();
+    
+    // Menu items
+    // Disabled header showing backend state; updated from update_tray_status
+    let status_i = MenuItem::with_id(app, "status", status_label(&SidecarStatus::Starting), false, None::<&str>)?;
+    // Enabled only while the sidecar is ready
+    let copy_url_i = MenuItem::with_id(app, "copy_url", "Copy Backend URL", false, None::<&str>)?;
+    let separator_i = PredefinedMenuItem::separator(app)?;
+    let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let all_workspaces_i = CheckMenuItem::with_id(app, "all_workspaces", "Show on All Workspaces", true, visible_on_all, None::<&str>)?;
+    let always_on_top_i = CheckMenuItem::with_id(app, "always_on_top", "Always on Top", true, always_on_top, None::<&str>)?;
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    // Developer option, hidden from release builds unless KEEP_SIDECAR_ON_EXIT is already set
+    let keep_on_exit = app.state::<SidecarState>().keep_on_exit();
+    let keep_sidecar_i = if cfg!(debug_assertions) || keep_on_exit {
+        Some(CheckMenuItem::with_id(app, "keep_sidecar", "Keep Backend Running on Quit", true, keep_on_exit, None::<&str>)?)
+    } else {
+        None
+    };
+
+    let mut items: Vec<&dyn IsMenuItem<R>> = vec![&status_i, &copy_url_i, &separator_i, &show_i, &all_workspaces_i, &always_on_top_i];
+    if let Some(item) = &keep_sidecar_i {
+        items.push(item);
+    }
+    items.push(&quit_i);
+    let menu = Menu::with_items(app, &items)?;
+
+    // The sidecar is spawned right after the tray, so start with the "starting" icon
+    let theme = current_theme(app);
+    let icon_size = tray_icon_size(app);
+    log::debug!("Using {}px tray icons", icon_size);
+    let icon = tray_icon_for(&SidecarStatus::Starting, theme, icon_size);
+
+    let click_action = TrayClickAction::from_env();
+    // Double-click force-shows the window regardless of focus (only reported on Windows)
+    let double_click_show = std::env::var("TRAY_DOUBLE_CLICK_SHOW")
+        .map(|v| v.trim() != "false" && v.trim() != "0")
+        .unwrap_or(true);
+    log::info!("Tray left-click action: {:?}", click_action);
+
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(icon)
+        .tooltip(tooltip_text(&SidecarStatus::Starting))
+        .menu(&menu)
+        .show_menu_on_left_click(click_action == TrayClickAction::Menu)
+        .on_menu_event(move |app, event| {
+            let event_id = event.id.as_ref();
+            if event_id == "show" {
+                show_main_window(app, VisibilitySource::Tray);
+            } else if event_id == "copy_url" {
+                copy_backend_url(app);
+            } else if event_id == "all_workspaces" {
+                if let Some(window) = app.get_webview_window("main") {
+                    let window_state = app.state::<WindowState>();
+                    let mut current = window_state.visible_on_all_workspaces.lock().unwrap();
+                    let new_value = !*current;
+                    
+                    if let Err(e) = window.set_visible_on_all_workspaces(new_value) {
+                        log::error!("Failed to set visible_on_all_workspaces: {:?}", e);
+                    } else {
+                        *current = new_value;
+                        log::info!("Set visible_on_all_workspaces to: {}", new_value);
+                        // Note: CheckMenuItem state is toggled automatically by the OS
+                    }
+                }
+            } else if event_id == "always_on_top" {
+                let current = *app.state::<WindowState>().always_on_top.lock().unwrap();
+                if let Err(e) = apply_always_on_top(app, !current) {
+                    log::error!("{}", e);
+                }
+            } else if event_id == "keep_sidecar" {
+                let keep = app.state::<SidecarState>().toggle_keep_on_exit();
+                log::info!("Keep sidecar running on quit: {}", keep);
+            } else if event_id == "quit" {
+                shutdown::quit(app);
+            }
+        })
+        .on_tray_icon_event(move |tray, event| match event {
+            TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } => match click_action {
+                TrayClickAction::Toggle => toggle_window_visibility(tray.app_handle(), VisibilitySource::Tray),
+                TrayClickAction::Show => show_main_window(tray.app_handle(), VisibilitySource::Tray),
+                TrayClickAction::Menu => {}
+            },
+            TrayIconEvent::DoubleClick {
+                button: MouseButton::Left,
+                ..
+            } if double_click_show => show_main_window(tray.app_handle(), VisibilitySource::Tray),
+            _ => {}
+        })
+        .build(app)?;
+
+    app.manage(TrayMenuState {
+        status_item: status_i,
+        copy_url_item: copy_url_i,
+        always_on_top_item: always_on_top_i,
+        status: Mutex::new(SidecarStatus::Starting),
+        theme: Mutex::new(theme),
+        icon_size: Mutex::new(icon_size),
+    });
+
+    Ok(())
+}
+
+/// Relabel the tray while quitting, since stopping the sidecar can take a
+/// couple of seconds and the app would otherwise look frozen
+pub fn show_shutting_down<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Some(menu_state) = app.try_state::<TrayMenuState<R>>() {
+        if let Err(e) = menu_state.status_item.set_text("Backend: shutting down…") {
+            log::warn!("Failed to update tray status item: {:?}", e);
+        }
+    }
+    if let Err(e) = tray.set_tooltip(Some("LightBot — shutting down backend…")) {
+        log::warn!("Failed to update tray tooltip: {:?}", e);
+    }
+}
+
+fn status_label(status: &SidecarStatus) -> String {
+    match status {
+        SidecarStatus::Starting => "Backend: starting…".to_string(),
+        SidecarStatus::Ready { port } => format!("Backend: ready (port {})", port),
+        SidecarStatus::Failed { .. } => "Backend: failed".to_string(),
+    }
+}
+
+fn tooltip_text(status: &SidecarStatus) -> String {
+    match status {
+        SidecarStatus::Starting => "LightBot — starting…".to_string(),
+        SidecarStatus::Ready { port } => format!("LightBot — running on port {}", port),
+        SidecarStatus::Failed { error, .. } => {
+            let mut shown: String = error.chars().take(TOOLTIP_MAX_ERROR_CHARS).collect();
+            if shown.len() < error.len() {
+                shown.push('…');
+            }
+            format!("LightBot — error: {}", shown)
+        }
+    }
+}
+
+/// Reflect sidecar health in the tray: the icon is greyed while starting,
+/// normal when ready, red-badged on failure; the tooltip and status item are relabeled
+pub fn update_tray_status<R: Runtime>(app: &tauri::AppHandle<R>, status: &SidecarStatus) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    let (theme, icon_size) = match app.try_state::<TrayMenuState<R>>() {
+        Some(menu_state) => {
+            *menu_state.status.lock().unwrap() = status.clone();
+            if let Err(e) = menu_state.status_item.set_text(status_label(status)) {
+                log::warn!("Failed to update tray status item: {:?}", e);
+            }
+            let ready = matches!(status, SidecarStatus::Ready { .. });
+            if let Err(e) = menu_state.copy_url_item.set_enabled(ready) {
+                log::warn!("Failed to update tray copy URL item: {:?}", e);
+            }
+            (*menu_state.theme.lock().unwrap(), *menu_state.icon_size.lock().unwrap())
+        }
+        None => (Theme::Light, FALLBACK_TRAY_ICON_SIZE),
+    };
+
+    if let Err(e) = tray.set_icon(Some(tray_icon_for(status, theme, icon_size))) {
+        log::warn!("Failed to update tray icon: {:?}", e);
+    }
+
+    if let Err(e) = tray.set_tooltip(Some(tooltip_text(status))) {
+        log::warn!("Failed to update tray tooltip: {:?}", e);
+    }
+}
+
+/// Put the sidecar's base URL on the clipboard for pasting into curl or a browser
+fn copy_backend_url<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<SidecarState>();
+    let port = state.get_port();
+    if port == 0 {
+        return;
+    }
+
+    let url = state.endpoint().base_url(port);
+    match app.clipboard().write_text(url.clone()) {
+        Ok(()) => log::info!("Copied backend URL {}", url),
+        Err(e) => log::warn!("Failed to copy backend URL: {}", e),
+    }
+}
+
+/// Swap the tray icon for the light or dark variant after the system appearance changes
+pub fn set_tray_theme<R: Runtime>(app: &tauri::AppHandle<R>, theme: Theme) {
+    let (Some(tray), Some(menu_state)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayMenuState<R>>()) else {
+        return;
+    };
+
+    *menu_state.theme.lock().unwrap() = theme;
+    let status = menu_state.status.lock().unwrap().clone();
+    let icon_size = *menu_state.icon_size.lock().unwrap();
+    log::debug!("System theme changed to {:?}, updating tray icon", theme);
+    if let Err(e) = tray.set_icon(Some(tray_icon_for(&status, theme, icon_size))) {
+        log::warn!("Failed to update tray icon: {:?}", e);
+    }
+}
+
+/// Re-pick the bundled icon size after a display's scale factor changes
+pub fn refresh_tray_scale<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let (Some(tray), Some(menu_state)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayMenuState<R>>()) else {
+        return;
+    };
+
+    let icon_size = tray_icon_size(app);
+    {
+        let mut current = menu_state.icon_size.lock().unwrap();
+        if *current == icon_size {
+            return;
+        }
+        *current = icon_size;
+    }
+    let status = menu_state.status.lock().unwrap().clone();
+    let theme = *menu_state.theme.lock().unwrap();
+    log::debug!("Display scale changed, switching to {}px tray icons", icon_size);
+    if let Err(e) = tray.set_icon(Some(tray_icon_for(&status, theme, icon_size))) {
+        log::warn!("Failed to update tray icon: {:?}", e);
+    }
+}
+
+/// Match the "Always on Top" checkbox to a change made outside the tray menu
+pub fn sync_always_on_top_item<R: Runtime>(app: &tauri::AppHandle<R>, on: bool) {
+    if let Some(menu_state) = app.try_state::<TrayMenuState<R>>() {
+        if let Err(e) = menu_state.always_on_top_item.set_checked(on) {
+            log::warn!("Failed to update tray always-on-top item: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tray_icon_size_follows_scale() {
+        assert_eq!(tray_icon_for_scale(1.0), 16);
+        assert_eq!(tray_icon_for_scale(1.5), 32);
+        assert_eq!(tray_icon_for_scale(2.0), 32);
+        assert_eq!(tray_icon_for_scale(3.0), 64);
+        assert_eq!(tray_icon_for_scale(0.0), FALLBACK_TRAY_ICON_SIZE);
+        assert_eq!(tray_icon_for_scale(f64::NAN), FALLBACK_TRAY_ICON_SIZE);
+    }
+}
@@ -0,0 +1,32 @@
+use serde::Serialize;
+
+/// Build information for bug reports
+#[derive(Clone, Serialize)]
+pub struct AppVersion {
+    pub version: &'static str,
+    /// Short git commit the app was built from, if known
+    pub commit: Option<&'static str>,
+    pub target: &'static str,
+}
+
+impl AppVersion {
+    pub fn current() -> Self {
+        let commit = env!("LIGHTBOT_GIT_COMMIT");
+        AppVersion {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: (!commit.is_empty()).then_some(commit),
+            target: env!("LIGHTBOT_TARGET"),
+        }
+    }
+}
+
+impl std::fmt::Display for AppVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{} ({}, {})", self.version, self.commit.unwrap_or("unknown commit"), self.target)
+    }
+}
+
+#[tauri::command]
+pub fn get_app_version() -> AppVersion {
+    AppVersion::current()
+}
@@ -0,0 +1,469 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, Monitor, PhysicalPosition, PhysicalSize, Runtime, Theme, WebviewWindow};
+
+use crate::{config, tray};
+
+/// Wait this long after the last move/resize before writing window.json
+const GEOMETRY_SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// What the window's close button does, from CLOSE_BEHAVIOR
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseBehavior {
+    /// Hide the window and keep running in the tray
+    Hide,
+    /// Close the window and exit the app
+    Quit,
+}
+
+impl CloseBehavior {
+    /// CLOSE_BEHAVIOR if set, otherwise the config file, otherwise hide
+    pub fn from_env(config: Option<Self>) -> Self {
+        let fallback = config.unwrap_or(Self::Hide);
+        match std::env::var("CLOSE_BEHAVIOR") {
+            Ok(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+                "quit" => Self::Quit,
+                "hide" => Self::Hide,
+                "" => fallback,
+                other => {
+                    log::warn!("Unknown CLOSE_BEHAVIOR '{}', expected hide|quit. Using {:?}.", other, fallback);
+                    fallback
+                }
+            },
+            Err(_) => fallback,
+        }
+    }
+}
+
+pub struct WindowState {
+    pub visible_on_all_workspaces: Mutex<bool>,
+    /// WINDOW_CENTER_ON_SHOW: move the window to the cursor's monitor every time it's shown
+    pub center_on_show: bool,
+    pub always_on_top: Mutex<bool>,
+    pub close_behavior: Mutex<CloseBehavior>,
+    /// Set by the tray's Quit item so CloseRequested lets the window close
+    pub really_quitting: AtomicBool,
+    /// Headless mode: the window is never shown
+    pub headless: bool,
+    /// No tray icon, so closing the window has to quit (there's no other Quit)
+    pub tray_disabled: bool,
+    /// Bumped on every move/resize; a pending save only runs if it's still the latest
+    pub geometry_generation: AtomicU64,
+}
+
+/// Main window position and size in physical pixels, persisted to ~/.lightbot/window.json
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct WindowGeometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+fn geometry_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lightbot").join("window.json"))
+}
+
+/// Initial always-on-top setting: WINDOW_ALWAYS_ON_TOP if set, otherwise the config file
+pub fn initial_always_on_top(config: Option<bool>) -> bool {
+    match std::env::var("WINDOW_ALWAYS_ON_TOP") {
+        Ok(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            "0" | "false" | "no" | "off" => false,
+            other => {
+                log::warn!("Ignoring invalid WINDOW_ALWAYS_ON_TOP '{}', expected true or false", other);
+                config.unwrap_or(false)
+            }
+        },
+        Err(_) => config.unwrap_or(false),
+    }
+}
+
+/// Float the main window above all others (or stop doing so) and keep the tray checkbox in sync
+pub fn set_window_always_on_top<R: Runtime>(app: &tauri::AppHandle<R>, on: bool) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("main") {
+        window
+            .set_always_on_top(on)
+            .map_err(|e| format!("Failed to set always_on_top: {:?}", e))?;
+    }
+
+    let window_state = app.state::<WindowState>();
+    *window_state.always_on_top.lock().map_err(|e| e.to_string())? = on;
+    tray::sync_always_on_top_item(app, on);
+    log::info!("Set always_on_top to: {}", on);
+    Ok(())
+}
+
+/// Like `set_window_always_on_top`, and remember the choice in config.toml
+pub fn apply_always_on_top<R: Runtime>(app: &tauri::AppHandle<R>, on: bool) -> Result<(), String> {
+    set_window_always_on_top(app, on)?;
+    config::update_config(app, |config| config.always_on_top = Some(on))
+}
+
+/// Read WINDOW_CENTER_ON_SHOW (off by default)
+pub fn center_on_show_from_env() -> bool {
+    std::env::var("WINDOW_CENTER_ON_SHOW")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// A display, as reported by `list_monitors`. Position and size are physical pixels.
+#[derive(Clone, Debug, Serialize)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+fn same_monitor(a: &Monitor, b: &Monitor) -> bool {
+    a.name() == b.name() && a.position() == b.position()
+}
+
+fn monitor_info(index: usize, monitor: &Monitor, primary: Option<&Monitor>) -> MonitorInfo {
+    MonitorInfo {
+        index,
+        name: monitor.name().cloned(),
+        width: monitor.size().width,
+        height: monitor.size().height,
+        x: monitor.position().x,
+        y: monitor.position().y,
+        scale_factor: monitor.scale_factor(),
+        is_primary: primary.is_some_and(|primary| same_monitor(monitor, primary)),
+    }
+}
+
+/// The monitor at `index`, or the primary one if there's no such monitor
+/// any more (e.g. a saved index after a display was unplugged)
+fn monitor_or_primary<R: Runtime>(window: &WebviewWindow<R>, index: usize) -> Option<Monitor> {
+    match window.available_monitors().unwrap_or_default().into_iter().nth(index) {
+        Some(monitor) => Some(monitor),
+        None => {
+            log::warn!("Monitor {} is not connected, using the primary monitor", index);
+            window.primary_monitor().ok().flatten()
+        }
+    }
+}
+
+/// Center the window in `monitor`'s work area
+fn center_in_monitor<R: Runtime>(window: &WebviewWindow<R>, monitor: &Monitor) {
+    let Ok(size) = window.outer_size() else {
+        return;
+    };
+    let area = monitor.work_area();
+    let x = area.position.x + (area.size.width.saturating_sub(size.width) / 2) as i32;
+    let y = area.position.y + (area.size.height.saturating_sub(size.height) / 2) as i32;
+    let _ = window.set_position(PhysicalPosition::new(x, y));
+}
+
+/// Move the window to the configured monitor at startup, unless the restored
+/// geometry already put it there
+pub fn apply_preferred_monitor<R: Runtime>(window: &WebviewWindow<R>, index: usize) {
+    let Some(target) = monitor_or_primary(window, index) else {
+        return;
+    };
+    let current = window.current_monitor().ok().flatten();
+    if !current.is_some_and(|current| same_monitor(&current, &target)) {
+        log::info!("Moving the window to monitor {:?}", target.name());
+        center_in_monitor(window, &target);
+    }
+}
+
+/// Center the window in the work area of the monitor under the mouse cursor,
+/// falling back to the window's current monitor if the cursor can't be located
+pub fn center_on_cursor_monitor<R: Runtime>(window: &WebviewWindow<R>) {
+    let cursor_monitor = window
+        .cursor_position()
+        .ok()
+        .and_then(|cursor| window.monitor_from_point(cursor.x, cursor.y).ok().flatten());
+    let monitor = match cursor_monitor {
+        Some(monitor) => Some(monitor),
+        None => window.current_monitor().ok().flatten(),
+    };
+    match monitor {
+        Some(monitor) if window.outer_size().is_ok() => center_in_monitor(window, &monitor),
+        _ => {
+            let _ = window.center();
+        }
+    }
+}
+
+/// What caused a show/hide, reported in the `window-visibility` event
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VisibilitySource {
+    Hotkey,
+    Tray,
+    Close,
+    /// The app was launched again while already running
+    #[serde(rename = "second-instance")]
+    SecondInstance,
+    /// Brought up by the first-run onboarding
+    #[serde(rename = "first-run")]
+    FirstRun,
+}
+
+/// Payload of the `window-visibility` event
+#[derive(Clone, Serialize)]
+struct WindowVisibility {
+    visible: bool,
+    source: VisibilitySource,
+}
+
+/// Tell the frontend the window was shown or hidden so it can pause work while hidden
+pub fn emit_visibility<R: Runtime, M: Emitter<R>>(emitter: &M, visible: bool, source: VisibilitySource) {
+    if let Err(e) = emitter.emit("window-visibility", WindowVisibility { visible, source }) {
+        log::error!("Failed to emit window-visibility event: {}", e);
+    }
+}
+
+/// System appearance as reported by the main window, or light where the
+/// platform doesn't report one
+pub fn current_theme<R: Runtime>(app: &tauri::AppHandle<R>) -> Theme {
+    app.get_webview_window("main")
+        .and_then(|window| window.theme().ok())
+        .unwrap_or(Theme::Light)
+}
+
+/// `light` or `dark`; Theme is non-exhaustive, so anything new counts as light
+fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+/// Tell the frontend the system appearance, as a `theme-changed` event with `light` or `dark`
+pub fn emit_theme<R: Runtime, M: Emitter<R>>(emitter: &M, theme: Theme) {
+    if let Err(e) = emitter.emit("theme-changed", theme_name(theme)) {
+        log::error!("Failed to emit theme-changed event: {}", e);
+    }
+}
+
+/// Show and focus the window, first moving it to the cursor's monitor if WINDOW_CENTER_ON_SHOW is set
+fn reveal_window<R: Runtime>(window: &WebviewWindow<R>) {
+    let window_state = window.state::<WindowState>();
+    if window_state.center_on_show {
+        center_on_cursor_monitor(window);
+    }
+    // Some window managers drop the always-on-top hint while the window is hidden
+    if *window_state.always_on_top.lock().unwrap() {
+        let _ = window.set_always_on_top(true);
+    }
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+pub fn show_main_window<R: Runtime>(app: &tauri::AppHandle<R>, source: VisibilitySource) {
+    if app.state::<WindowState>().headless {
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        reveal_window(&window);
+        emit_visibility(app, true, source);
+    }
+}
+
+pub fn toggle_window_visibility<R: Runtime>(app: &tauri::AppHandle<R>, source: VisibilitySource) {
+    if app.state::<WindowState>().headless {
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let is_visible = window.is_visible().unwrap_or(false);
+        let is_focused = window.is_focused().unwrap_or(false);
+
+        if is_visible && is_focused {
+            let _ = window.hide();
+            emit_visibility(app, false, source);
+        } else {
+            reveal_window(&window);
+            emit_visibility(app, true, source);
+        }
+    }
+}
+
+/// Fit `geometry` inside `area` (a monitor's work area), shrinking it if it's larger
+fn clamp_to_area(
+    geometry: WindowGeometry,
+    area_pos: PhysicalPosition<i32>,
+    area_size: PhysicalSize<u32>,
+) -> WindowGeometry {
+    let width = geometry.width.min(area_size.width);
+    let height = geometry.height.min(area_size.height);
+    let max_x = area_pos.x + (area_size.width - width) as i32;
+    let max_y = area_pos.y + (area_size.height - height) as i32;
+
+    WindowGeometry {
+        x: geometry.x.clamp(area_pos.x, max_x),
+        y: geometry.y.clamp(area_pos.y, max_y),
+        width,
+        height,
+    }
+}
+
+fn read_saved_geometry() -> Option<WindowGeometry> {
+    let path = geometry_file()?;
+    let contents = std::fs::read_to_string(&path).ok()?;
+    match serde_json::from_str::<WindowGeometry>(&contents) {
+        Ok(geometry) if geometry.width > 0 && geometry.height > 0 => Some(geometry),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Ignoring invalid window geometry in {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Restore the saved position and size, clamped to the monitor it was on.
+/// Centers the window if nothing was saved or that monitor is gone.
+/// With WINDOW_CENTER_ON_SHOW the position is overridden again when the window is shown.
+pub fn restore_window_geometry<R: Runtime>(window: &WebviewWindow<R>) {
+    let Some(saved) = read_saved_geometry() else {
+        let _ = window.center();
+        return;
+    };
+
+    let center_x = saved.x + (saved.width / 2) as i32;
+    let center_y = saved.y + (saved.height / 2) as i32;
+    let monitor = window.available_monitors().unwrap_or_default().into_iter().find(|m| {
+        let area = m.work_area();
+        center_x >= area.position.x
+            && center_x < area.position.x + area.size.width as i32
+            && center_y >= area.position.y
+            && center_y < area.position.y + area.size.height as i32
+    });
+
+    let Some(monitor) = monitor else {
+        log::info!("Saved window position is off-screen, centering instead");
+        let _ = window.center();
+        return;
+    };
+
+    let area = monitor.work_area();
+    let geometry = clamp_to_area(saved, area.position, area.size);
+    log::debug!("Restoring window geometry: {:?}", geometry);
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+}
+
+fn save_window_geometry<R: Runtime>(window: &tauri::Window<R>) {
+    // Minimized windows report bogus positions on some platforms
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+        return;
+    };
+    let Some(path) = geometry_file() else {
+        return;
+    };
+
+    let geometry = WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    };
+
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    match serde_json::to_string(&geometry) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                log::warn!("Failed to save window geometry to {:?}: {}", path, e);
+            }
+        }
+        Err(e) => log::warn!("Failed to serialize window geometry: {}", e),
+    }
+}
+
+/// Save the geometry once moves/resizes have settled for a moment
+pub fn schedule_geometry_save<R: Runtime>(window: &tauri::Window<R>) {
+    let window_state = window.state::<WindowState>();
+    let generation = window_state.geometry_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let window = window.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(GEOMETRY_SAVE_DEBOUNCE).await;
+        let window_state = window.state::<WindowState>();
+        if window_state.geometry_generation.load(Ordering::SeqCst) == generation {
+            save_window_geometry(&window);
+        }
+    });
+}
+
+/// Whether a close request should hide the window instead of closing it
+pub fn should_hide_on_close<R: Runtime>(window: &tauri::Window<R>) -> bool {
+    let window_state = window.state::<WindowState>();
+    !window_state.tray_disabled
+        && *window_state.close_behavior.lock().unwrap() == CloseBehavior::Hide
+        && !window_state.really_quitting.load(Ordering::SeqCst)
+}
+
+#[tauri::command]
+pub fn set_visible_on_all_workspaces(
+    app: tauri::AppHandle,
+    visible: bool,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("main") {
+        window.set_visible_on_all_workspaces(visible)
+            .map_err(|e| format!("Failed to set visible_on_all_workspaces: {:?}", e))?;
+        log::info!("Set visible_on_all_workspaces to: {}", visible);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_always_on_top(app: tauri::AppHandle, on: bool) -> Result<(), String> {
+    apply_always_on_top(&app, on)
+}
+
+#[tauri::command]
+pub fn list_monitors(app: tauri::AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    let monitors = app.available_monitors().map_err(|e| format!("Failed to list monitors: {}", e))?;
+    let primary = app.primary_monitor().ok().flatten();
+    Ok(monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| monitor_info(index, monitor, primary.as_ref()))
+        .collect())
+}
+
+/// Center the main window on monitor `index` (from `list_monitors`) and
+/// remember it for the next launch. Falls back to the primary monitor if
+/// `index` doesn't exist, and remembers that one instead. Returns the monitor
+/// the window was moved to.
+#[tauri::command]
+pub fn move_window_to_monitor(app: tauri::AppHandle, index: usize) -> Result<MonitorInfo, String> {
+    let window = app.get_webview_window("main").ok_or("Main window not found")?;
+    let monitor = monitor_or_primary(&window, index).ok_or("No monitor found")?;
+    center_in_monitor(&window, &monitor);
+
+    let monitors = window.available_monitors().unwrap_or_default();
+    let primary = window.primary_monitor().ok().flatten();
+    let Some(actual) = monitors.iter().position(|m| same_monitor(m, &monitor)) else {
+        log::warn!("Moved the window to {:?}, which isn't in the monitor list, not saving it", monitor.name());
+        return Ok(monitor_info(index, &monitor, primary.as_ref()));
+    };
+    log::info!("Moved the window to monitor {} ({:?})", actual, monitor.name());
+    // Save where the window went, so an out-of-range index isn't retried on every launch
+    config::update_config(&app, |config| config.monitor = Some(actual))?;
+
+    Ok(monitor_info(actual, &monitor, primary.as_ref()))
+}
+
+/// Current system appearance, for a UI that missed the startup `theme-changed` event
+#[tauri::command]
+pub fn get_theme(app: tauri::AppHandle) -> &'static str {
+    theme_name(current_theme(&app))
+}
@@ -0,0 +1,36 @@
+#!/bin/sh
+# Stand-in for the Python sidecar in the sidecar.rs tests.
+# Usage: mock_sidecar.sh --port <port> [--mode ready|crash|unhealthy]
+#   ready      serve 200 on /health
+#   crash      exit with code 3 without binding
+#   unhealthy  serve 503 on /health
+exec python3 - "$@" <<'PY'
+import argparse
+import json
+import sys
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+parser = argparse.ArgumentParser()
+parser.add_argument("--port", type=int, required=True)
+parser.add_argument("--mode", default="ready")
+args, _ = parser.parse_known_args()
+
+if args.mode == "crash":
+    sys.exit(3)
+
+
+class Handler(BaseHTTPRequestHandler):
+    def do_GET(self):
+        healthy = args.mode == "ready" and self.path == "/health"
+        body = json.dumps({"status": "healthy" if healthy else "initializing", "progress": 100 if healthy else 40})
+        self.send_response(200 if healthy else 503)
+        self.send_header("Content-Type", "application/json")
+        self.end_headers()
+        self.wfile.write(body.encode())
+
+    def log_message(self, *_):
+        pass
+
+
+HTTPServer(("127.0.0.1", args.port), Handler).serve_forever()
+PY
@@ -1,6 +1,7 @@
 import { useState, useEffect } from "react";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import ChatWindow from "./components/ChatWindow";
 import SettingsPanel from "./components/SettingsPanel";
 import TitleBar, { type ModelConfig } from "./components/TitleBar";
@@ -50,8 +51,22 @@ function App() {
     return () => mediaQuery.removeEventListener("change", handleChange);
   }, []);
 
+  // The OS appearance as seen by Tauri, which some webviews don't report via matchMedia
+  useEffect(() => {
+    invoke<"light" | "dark">("get_theme")
+      .then(setTheme)
+      .catch((e) => console.error("Failed to get system theme:", e));
+
+    const unlisten = listen<"light" | "dark">("theme-changed", (event) => {
+      setTheme(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const [selectedModelIndex, setSelectedModelIndex] = useState(0);
-  const { isReady, error, port: sidecarPort } = useSidecar();
+  const { isReady, error, port: sidecarPort, errorDetail, progress } = useSidecar();
 
   // Multi-session state management
   const { sessions, activeSessionId, createSession, deleteSession, switchSession } = useChatSessions();
@@ -235,7 +250,19 @@ function App() {
       <div className="flex-1 relative">
         {/* Chat Window - fills container */}
         <div className="absolute inset-0">
-          {isLoading ? (
+          {errorDetail?.kind === "BinaryNotFound" ? (
+            <div className="h-full flex items-center justify-center p-6">
+              <div className="max-w-md text-center">
+                <p className="text-text-muted text-sm mb-2">
+                  The LightBot backend hasn't been built yet.
+                </p>
+                <p className="text-text-muted text-sm mb-2">{errorDetail.buildHint}</p>
+                <p className="text-text-disabled text-xs break-all">
+                  Expected at {errorDetail.expectedPath}
+                </p>
+              </div>
+            </div>
+          ) : isLoading ? (
             <div className="h-full flex items-center justify-center">
               <div className="text-center">
                 <div className="flex items-center justify-center gap-1 text-text-muted mb-2">
@@ -246,6 +273,14 @@ function App() {
                 <p className="text-text-muted text-sm">
                   {error ? `Error: ${error}` : "Initializing LightBot..."}
                 </p>
+                {!error && progress !== null && (
+                  <div className="w-48 h-1 mt-2 mx-auto bg-surface-tertiary">
+                    <div
+                      className="h-full bg-text-muted transition-all"
+                      style={{ width: `${progress}%` }}
+                    />
+                  </div>
+                )}
                 {sidecarPort && (
                   <p className="text-text-disabled text-xs mt-1">
                     Connected on port {sidecarPort}
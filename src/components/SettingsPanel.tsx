@@ -42,6 +42,24 @@ export default function SettingsPanel({ onClose, fontSize, onFontSizeChange, api
   const [activeTab, setActiveTab] = useState<"general" | "llm" | "search">("llm");
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [launchAtLogin, setLaunchAtLogin] = useState(false);
+
+  // Launch at login is owned by the Rust side, not the sidecar's settings
+  useEffect(() => {
+    invoke<boolean>("get_autostart")
+      .then(setLaunchAtLogin)
+      .catch((e) => console.error("Failed to read launch at login:", e));
+  }, []);
+
+  const toggleLaunchAtLogin = async (enabled: boolean) => {
+    try {
+      await invoke("set_autostart", { enabled });
+      setLaunchAtLogin(enabled);
+    } catch (e) {
+      console.error("Failed to update launch at login:", e);
+      setError("Failed to update launch at login");
+    }
+  };
 
   // Fetch settings from backend on mount
   useEffect(() => {
@@ -237,6 +255,23 @@ export default function SettingsPanel({ onClose, fontSize, onFontSizeChange, api
               </div>
             </div>
 
+            {/* Launch at Login */}
+            <div className="pt-3 border-t border-border-subtle/30">
+              <label className="block text-text-disabled text-[10px] font-bold uppercase tracking-wider mb-0.5">Launch at Login</label>
+              <div className="flex items-center justify-between">
+                <div className="text-xs text-text-muted">Start LightBot when you log in</div>
+                <label className="relative inline-flex items-center cursor-pointer group">
+                  <input
+                    type="checkbox"
+                    checked={launchAtLogin}
+                    onChange={(e) => toggleLaunchAtLogin(e.target.checked)}
+                    className="sr-only peer"
+                  />
+                  <div className="w-9 h-5 bg-surface-tertiary rounded-full peer peer-checked:bg-accent after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-text-muted after:rounded-full after:h-4 after:w-4 after:transition-all peer-checked:after:translate-x-4 peer-checked:after:bg-white group-hover:after:bg-text-secondary"></div>
+                </label>
+              </div>
+            </div>
+
             {/* Global Hotkey */}
             <div className="pt-3 border-t border-border-subtle/30 space-y-2">
               <div>
@@ -2,10 +2,64 @@ import { useState, useEffect } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
 
+export interface HealthInfo {
+  status: string;
+  version: string | null;
+  error: string | null;
+  progress?: number | null;
+  app?: string;
+  pid?: number | null;
+  ppid?: number | null;
+}
+
+export type HealthFailure = "connection_refused" | "timeout" | "status" | "transport";
+
+export type SidecarErrorPayload =
+  | {
+      kind: "BinaryNotFound";
+      message: string;
+      expectedPath: string;
+      triedPaths: string[];
+      buildHint: string;
+    }
+  | {
+      kind: "HealthTimeout";
+      message: string;
+      timeoutSecs: number;
+      lastFailure: HealthFailure | null;
+    }
+  | { kind: "Exited"; message: string; exit: unknown }
+  | { kind: "Failed"; message: string };
+
+type SidecarStatus =
+  | { status: "starting" }
+  | { status: "ready"; port: number }
+  | { status: "failed"; error: string; exit: unknown };
+
+interface SidecarFullStatus {
+  status: SidecarStatus;
+  port: number;
+  health: HealthInfo | null;
+  progress: number | null;
+  error_detail: SidecarErrorPayload | null;
+  managed: boolean;
+}
+
+interface SidecarReadyPayload {
+  port: number;
+  health: HealthInfo | null;
+}
+
 interface SidecarState {
   isReady: boolean;
   error: string | null;
   port: number | null;
+  health: HealthInfo | null;
+  errorDetail: SidecarErrorPayload | null;
+  /** Startup progress (0–100), or null when the sidecar doesn't report one */
+  progress: number | null;
+  /** Set when the watchdog found the sidecar unresponsive and is restarting it */
+  warning: string | null;
 }
 
 export function useSidecar(): SidecarState {
@@ -13,43 +67,85 @@ export function useSidecar(): SidecarState {
     isReady: false,
     error: null,
     port: null,
+    health: null,
+    errorDetail: null,
+    progress: null,
+    warning: null,
   });
 
   useEffect(() => {
     let unlistenReady: (() => void) | null = null;
     let unlistenError: (() => void) | null = null;
+    let unlistenProgress: (() => void) | null = null;
+    let unlistenUnhealthy: (() => void) | null = null;
 
     const setupListeners = async () => {
-      // First, try to get current status in case it's already ready
-      try {
-        const port = await invoke<number>("get_sidecar_status");
-        setState({
-          isReady: true,
-          error: null,
-          port: port,
-        });
-      } catch (e) {
-        // Not ready yet or error, that's fine, we'll wait for events
-        if (typeof e === "string" && e !== "Sidecar not started yet") {
-          setState((s) => ({ ...s, error: e }));
-        }
-      }
-
-      unlistenReady = await listen("sidecar-ready", (event) => {
+      unlistenReady = await listen<SidecarReadyPayload>("sidecar-ready", (event) => {
         setState({
           isReady: true,
           error: null,
-          port: event.payload as number,
+          port: event.payload.port,
+          health: event.payload.health,
+          errorDetail: null,
+          progress: null,
+          warning: null,
         });
       });
 
-      unlistenError = await listen("sidecar-error", (event) => {
+      unlistenError = await listen<SidecarErrorPayload>("sidecar-error", (event) => {
         setState({
           isReady: false,
-          error: event.payload as string,
+          error: event.payload.message,
           port: null,
+          health: null,
+          errorDetail: event.payload,
+          progress: null,
+          warning: null,
         });
       });
+
+      unlistenProgress = await listen<{ progress: number | null }>("sidecar-progress", (event) => {
+        setState((s) => ({ ...s, progress: event.payload.progress }));
+      });
+
+      unlistenUnhealthy = await listen<{ failures: number; reason: HealthFailure; error: string }>(
+        "sidecar-unhealthy",
+        (event) => {
+          setState((s) => ({
+            ...s,
+            warning: `Backend stopped responding (${event.payload.error}), restarting...`,
+          }));
+        }
+      );
+
+      // Catch up on anything sent before the listeners were registered
+      try {
+        const full = await invoke<SidecarFullStatus>("get_full_status");
+        if (full.status.status === "ready") {
+          setState((s) => ({
+            ...s,
+            isReady: true,
+            error: null,
+            port: full.port,
+            health: full.health,
+            errorDetail: null,
+            progress: null,
+          }));
+        } else if (full.status.status === "failed") {
+          const error = full.error_detail?.message ?? full.status.error;
+          setState((s) => ({
+            ...s,
+            isReady: false,
+            error,
+            port: null,
+            errorDetail: full.error_detail,
+          }));
+        } else {
+          setState((s) => ({ ...s, progress: full.progress }));
+        }
+      } catch (e) {
+        console.error("Failed to get sidecar status:", e);
+      }
     };
 
     setupListeners();
@@ -57,6 +153,8 @@ export function useSidecar(): SidecarState {
     return () => {
       if (unlistenReady) unlistenReady();
       if (unlistenError) unlistenError();
+      if (unlistenProgress) unlistenProgress();
+      if (unlistenUnhealthy) unlistenUnhealthy();
     };
   }, []);
 